@@ -0,0 +1,140 @@
+// chain.rs
+
+use ethers::{
+    types::{Address, H256, U256},
+    utils::to_checksum,
+};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+const GWEI: u64 = 1_000_000_000;
+
+/// Static description of an EVM chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChainInfo {
+    pub name: &'static str,
+    pub chain_id: u64,
+    pub native_symbol: &'static str,
+    /// WETH-style wrapper of the native coin that V2 routers swap through.
+    pub wrapped_native_address: Address,
+    pub block_time: Duration,
+    /// Whether the chain prices gas with a base fee and priority fee.
+    pub eip1559: bool,
+    /// Lowest priority fee, in wei, the chain's validators accept.
+    pub min_priority_fee: U256,
+    /// Explorer link with `{kind}` (`tx` or `address`) and `{id}` placeholders.
+    pub explorer_url_template: &'static str,
+    /// Confirmations to wait for before a transaction is treated as final.
+    pub confirmations: usize,
+}
+
+impl ChainInfo {
+    pub fn all() -> &'static [ChainInfo] {
+        static CHAINS: OnceLock<Vec<ChainInfo>> = OnceLock::new();
+        CHAINS.get_or_init(|| {
+            vec![
+                ChainInfo {
+                    name: "Ethereum",
+                    chain_id: 1,
+                    native_symbol: "ETH",
+                    wrapped_native_address: address("0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"),
+                    block_time: Duration::from_secs(12),
+                    eip1559: true,
+                    min_priority_fee: U256::zero(),
+                    explorer_url_template: "https://etherscan.io/{kind}/{id}",
+                    confirmations: 1,
+                },
+                ChainInfo {
+                    name: "BNB Smart Chain",
+                    chain_id: 56,
+                    native_symbol: "BNB",
+                    wrapped_native_address: address("0xbb4CdB9CBd36B01bD1cBaEBF2De08d9173bc095c"),
+                    block_time: Duration::from_secs(3),
+                    eip1559: false,
+                    min_priority_fee: U256::zero(),
+                    explorer_url_template: "https://bscscan.com/{kind}/{id}",
+                    confirmations: 1,
+                },
+                ChainInfo {
+                    name: "Polygon",
+                    chain_id: 137,
+                    native_symbol: "POL",
+                    wrapped_native_address: address("0x0d500B1d8E8eF31E21C99d1Db9A6444d3ADf1270"),
+                    block_time: Duration::from_secs(2),
+                    eip1559: true,
+                    // Validators drop transactions tipping less than 30 gwei
+                    min_priority_fee: U256::from(30 * GWEI),
+                    explorer_url_template: "https://polygonscan.com/{kind}/{id}",
+                    // Short reorgs are common enough to wait a few blocks
+                    confirmations: 3,
+                },
+                ChainInfo {
+                    name: "Base",
+                    chain_id: 8453,
+                    native_symbol: "ETH",
+                    wrapped_native_address: address("0x4200000000000000000000000000000000000006"),
+                    block_time: Duration::from_secs(2),
+                    eip1559: true,
+                    min_priority_fee: U256::zero(),
+                    explorer_url_template: "https://basescan.org/{kind}/{id}",
+                    confirmations: 1,
+                },
+                ChainInfo {
+                    name: "Arbitrum One",
+                    chain_id: 42161,
+                    native_symbol: "ETH",
+                    wrapped_native_address: address("0x82aF49447D8a07e3bd95BD0d56f35241523fBab1"),
+                    block_time: Duration::from_millis(250),
+                    eip1559: true,
+                    min_priority_fee: U256::zero(),
+                    explorer_url_template: "https://arbiscan.io/{kind}/{id}",
+                    confirmations: 1,
+                },
+                ChainInfo {
+                    name: "Optimism",
+                    chain_id: 10,
+                    native_symbol: "ETH",
+                    wrapped_native_address: address("0x4200000000000000000000000000000000000006"),
+                    block_time: Duration::from_secs(2),
+                    eip1559: true,
+                    min_priority_fee: U256::zero(),
+                    explorer_url_template: "https://optimistic.etherscan.io/{kind}/{id}",
+                    confirmations: 1,
+                },
+                ChainInfo {
+                    name: "Avalanche C-Chain",
+                    chain_id: 43114,
+                    native_symbol: "AVAX",
+                    wrapped_native_address: address("0xB31f66AA3C1e785363F0875A1B74E27b85FD66c7"),
+                    block_time: Duration::from_secs(2),
+                    eip1559: true,
+                    min_priority_fee: U256::zero(),
+                    explorer_url_template: "https://snowtrace.io/{kind}/{id}",
+                    confirmations: 1,
+                },
+            ]
+        })
+    }
+
+    pub fn by_chain_id(chain_id: u64) -> Option<&'static ChainInfo> {
+        Self::all().iter().find(|info| info.chain_id == chain_id)
+    }
+
+    pub fn explorer_tx_url(&self, transaction_hash: H256) -> String {
+        self.explorer_url("tx", &format!("{:?}", transaction_hash))
+    }
+
+    pub fn explorer_address_url(&self, address: Address) -> String {
+        self.explorer_url("address", &to_checksum(&address, None))
+    }
+
+    fn explorer_url(&self, kind: &str, id: &str) -> String {
+        self.explorer_url_template
+            .replace("{kind}", kind)
+            .replace("{id}", id)
+    }
+}
+
+fn address(address: &str) -> Address {
+    address.parse().expect("valid registry address")
+}
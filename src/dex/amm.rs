@@ -0,0 +1,302 @@
+// amm.rs
+
+use ethers::types::{U256, U512};
+use std::error::Error;
+use std::fmt;
+
+/// Fee denominator used throughout: fees are expressed in basis points.
+const BPS_DENOMINATOR: u32 = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AmmError {
+    InsufficientInputAmount,
+    InsufficientOutputAmount,
+    InsufficientLiquidity,
+    /// A fee of 100% or more leaves nothing to trade.
+    InvalidFee(u32),
+    /// A price-impact budget outside `(0, 1)`.
+    InvalidPriceImpact,
+    Overflow,
+}
+
+impl fmt::Display for AmmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AmmError::InsufficientInputAmount => write!(f, "Insufficient input amount"),
+            AmmError::InsufficientOutputAmount => write!(f, "Insufficient output amount"),
+            AmmError::InsufficientLiquidity => write!(f, "Insufficient liquidity"),
+            AmmError::InvalidFee(fee_bps) => write!(f, "Invalid fee of {} bps", fee_bps),
+            AmmError::InvalidPriceImpact => {
+                write!(f, "Price impact budget must be between 0 and 1")
+            }
+            AmmError::Overflow => write!(f, "Amount overflows U256"),
+        }
+    }
+}
+
+impl Error for AmmError {}
+
+/// One pool along a route, oriented in the swap direction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hop {
+    pub reserve_in: U256,
+    pub reserve_out: U256,
+    pub fee_bps: u32,
+}
+
+impl Hop {
+    pub fn new(reserve_in: U256, reserve_out: U256, fee_bps: u32) -> Self {
+        Self {
+            reserve_in,
+            reserve_out,
+            fee_bps,
+        }
+    }
+
+    pub fn amount_out(&self, amount_in: U256) -> Result<U256, AmmError> {
+        get_amount_out(amount_in, self.reserve_in, self.reserve_out, self.fee_bps)
+    }
+
+    pub fn amount_in(&self, amount_out: U256) -> Result<U256, AmmError> {
+        get_amount_in(amount_out, self.reserve_in, self.reserve_out, self.fee_bps)
+    }
+
+    /// The pool after a swap of `amount_in` for `amount_out` has settled.
+    pub fn after_swap(&self, amount_in: U256, amount_out: U256) -> Result<Hop, AmmError> {
+        Ok(Hop {
+            reserve_in: self
+                .reserve_in
+                .checked_add(amount_in)
+                .ok_or(AmmError::Overflow)?,
+            reserve_out: self
+                .reserve_out
+                .checked_sub(amount_out)
+                .ok_or(AmmError::InsufficientLiquidity)?,
+            fee_bps: self.fee_bps,
+        })
+    }
+}
+
+fn fee_multiplier(fee_bps: u32) -> Result<U256, AmmError> {
+    if fee_bps >= BPS_DENOMINATOR {
+        return Err(AmmError::InvalidFee(fee_bps));
+    }
+    Ok(U256::from(BPS_DENOMINATOR - fee_bps))
+}
+
+/// Lossy conversion for price ratios, which only need float precision.
+pub(crate) fn to_f64(value: U256) -> f64 {
+    value.to_string().parse().unwrap_or(f64::INFINITY)
+}
+
+fn to_u256(value: U512) -> Result<U256, AmmError> {
+    U256::try_from(value).map_err(|_| AmmError::Overflow)
+}
+
+/// `UniswapV2Library.getAmountOut` with the fee in basis points. Forks that
+/// use a 1000 denominator (997/1000, 998/1000) round identically because
+/// numerator and denominator are scaled by the same factor.
+pub fn get_amount_out(
+    amount_in: U256,
+    reserve_in: U256,
+    reserve_out: U256,
+    fee_bps: u32,
+) -> Result<U256, AmmError> {
+    if amount_in.is_zero() {
+        return Err(AmmError::InsufficientInputAmount);
+    }
+    if reserve_in.is_zero() || reserve_out.is_zero() {
+        return Err(AmmError::InsufficientLiquidity);
+    }
+
+    let amount_in_with_fee = amount_in.full_mul(fee_multiplier(fee_bps)?);
+    let numerator = amount_in_with_fee * U512::from(reserve_out);
+    let denominator = reserve_in.full_mul(U256::from(BPS_DENOMINATOR)) + amount_in_with_fee;
+    to_u256(numerator / denominator)
+}
+
+/// `UniswapV2Library.getAmountIn` with the fee in basis points.
+pub fn get_amount_in(
+    amount_out: U256,
+    reserve_in: U256,
+    reserve_out: U256,
+    fee_bps: u32,
+) -> Result<U256, AmmError> {
+    if amount_out.is_zero() {
+        return Err(AmmError::InsufficientOutputAmount);
+    }
+    if reserve_in.is_zero() || reserve_out.is_zero() || amount_out >= reserve_out {
+        return Err(AmmError::InsufficientLiquidity);
+    }
+
+    let numerator = reserve_in.full_mul(amount_out) * U512::from(BPS_DENOMINATOR);
+    let denominator = (reserve_out - amount_out).full_mul(fee_multiplier(fee_bps)?);
+    to_u256(numerator / denominator + U512::one())
+}
+
+/// `UniswapV2Library.quote`: the amount of B worth `amount_a` at the pool's
+/// current price, without fee or price impact.
+pub fn quote(amount_a: U256, reserve_a: U256, reserve_b: U256) -> Result<U256, AmmError> {
+    if amount_a.is_zero() {
+        return Err(AmmError::InsufficientInputAmount);
+    }
+    if reserve_a.is_zero() || reserve_b.is_zero() {
+        return Err(AmmError::InsufficientLiquidity);
+    }
+    to_u256(amount_a.full_mul(reserve_b) / U512::from(reserve_a))
+}
+
+/// Chains `get_amount_out` over `hops` like the router's `getAmountsOut`; the
+/// first element is `amount_in`.
+pub fn get_amounts_out(amount_in: U256, hops: &[Hop]) -> Result<Vec<U256>, AmmError> {
+    let mut amounts = Vec::with_capacity(hops.len() + 1);
+    amounts.push(amount_in);
+    for hop in hops {
+        let amount_out = hop.amount_out(amounts[amounts.len() - 1])?;
+        amounts.push(amount_out);
+    }
+    Ok(amounts)
+}
+
+/// Chains `get_amount_in` backwards over `hops` like the router's
+/// `getAmountsIn`; the last element is `amount_out`.
+pub fn get_amounts_in(amount_out: U256, hops: &[Hop]) -> Result<Vec<U256>, AmmError> {
+    let mut amounts = vec![U256::zero(); hops.len() + 1];
+    amounts[hops.len()] = amount_out;
+    for (index, hop) in hops.iter().enumerate().rev() {
+        amounts[index] = hop.amount_in(amounts[index + 1])?;
+    }
+    Ok(amounts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ether(amount: u64) -> U256 {
+        U256::exp10(18) * amount
+    }
+
+    // Expected values are `UniswapV2Library` results for 1 token in against
+    // reserves of 100/200, using each fork's own fee constants.
+
+    #[test]
+    fn amount_out_matches_uniswap_997_1000() {
+        assert_eq!(
+            get_amount_out(ether(1), ether(100), ether(200), 30),
+            Ok(U256::from(1_974_316_068_794_122_597u64))
+        );
+    }
+
+    #[test]
+    fn amount_out_matches_pancakeswap_9975_10000() {
+        assert_eq!(
+            get_amount_out(ether(1), ether(100), ether(200), 25),
+            Ok(U256::from(1_975_296_418_228_173_964u64))
+        );
+    }
+
+    #[test]
+    fn amount_out_matches_biswap_swap_fee_2() {
+        // swapFee() = 2 tenths of a percent, i.e. 998/1000
+        assert_eq!(
+            get_amount_out(ether(1), ether(100), ether(200), 2 * 10),
+            Ok(U256::from(1_976_276_757_955_603_081u64))
+        );
+    }
+
+    #[test]
+    fn amount_in_matches_uniswap_997_1000() {
+        assert_eq!(
+            get_amount_in(ether(1), ether(100), ether(200), 30),
+            Ok(U256::from(504_024_636_724_243_082u64))
+        );
+    }
+
+    #[test]
+    fn amount_in_rounds_up_even_when_exact() {
+        // 997 * 1 * 1000 / (1 * 997) divides evenly; the router still adds one
+        assert_eq!(
+            get_amount_in(U256::from(1), U256::from(997), U256::from(2), 30),
+            Ok(U256::from(1001))
+        );
+        assert_eq!(
+            get_amount_in(
+                U256::from(1000),
+                U256::from(1_000_000),
+                U256::from(1_000_000),
+                30
+            ),
+            Ok(U256::from(1005))
+        );
+    }
+
+    #[test]
+    fn amounts_in_round_trip_through_amounts_out() {
+        let hops = [
+            Hop::new(ether(100), ether(200), 30),
+            Hop::new(ether(5_000), ether(3_000), 25),
+        ];
+        let amount_out = ether(2);
+
+        let amounts_in = get_amounts_in(amount_out, &hops).unwrap();
+        let amounts_out = get_amounts_out(amounts_in[0], &hops).unwrap();
+        assert!(amounts_out[2] >= amount_out);
+
+        // One unit less input no longer buys the requested output
+        let short = get_amounts_out(amounts_in[0] - 1, &hops).unwrap();
+        assert!(short[2] < amount_out);
+    }
+
+    #[test]
+    fn rejects_fee_of_100_percent_or_more() {
+        assert_eq!(
+            get_amount_out(ether(1), ether(100), ether(200), 10_000),
+            Err(AmmError::InvalidFee(10_000))
+        );
+        assert_eq!(
+            get_amount_in(ether(1), ether(100), ether(200), 12_000),
+            Err(AmmError::InvalidFee(12_000))
+        );
+    }
+
+    #[test]
+    fn rejects_zero_reserves() {
+        assert_eq!(
+            get_amount_out(ether(1), U256::zero(), ether(200), 30),
+            Err(AmmError::InsufficientLiquidity)
+        );
+        assert_eq!(
+            get_amount_in(ether(1), ether(100), U256::zero(), 30),
+            Err(AmmError::InsufficientLiquidity)
+        );
+        assert_eq!(
+            quote(ether(1), ether(100), U256::zero()),
+            Err(AmmError::InsufficientLiquidity)
+        );
+    }
+
+    #[test]
+    fn rejects_output_at_or_above_reserve() {
+        assert_eq!(
+            get_amount_in(ether(200), ether(100), ether(200), 30),
+            Err(AmmError::InsufficientLiquidity)
+        );
+        assert_eq!(
+            get_amount_in(ether(201), ether(100), ether(200), 30),
+            Err(AmmError::InsufficientLiquidity)
+        );
+    }
+
+    #[test]
+    fn rejects_zero_amounts() {
+        assert_eq!(
+            get_amount_out(U256::zero(), ether(100), ether(200), 30),
+            Err(AmmError::InsufficientInputAmount)
+        );
+        assert_eq!(
+            get_amount_in(U256::zero(), ether(100), ether(200), 30),
+            Err(AmmError::InsufficientOutputAmount)
+        );
+    }
+}
@@ -1,63 +1,32 @@
-// pancakeswap.rs
+// apeswap.rs
 
-use super::dex::BaseDex;
-use super::Dex;
-use async_trait::async_trait;
-use ethers::{prelude::*, types::Address};
-use std::error::Error;
+use super::uniswap_v2::{UniswapV2Config, UniswapV2Dex};
+use crate::token::token::BlockChain;
+use ethers::{providers::Middleware, types::Address};
 use std::sync::Arc;
 
-#[derive(Debug, Clone)]
-pub struct ApeSwap {
-    base_dex: BaseDex,
-}
+static APESWAP_ROUTER_ABI_JSON: &[u8] = include_bytes!("../../resources/ApeSwapRouterABI.json");
 
-static APESWAP_ROUTER_ABI_JSON: &'static [u8] =
-    include_bytes!("../../resources/ApeSwapRouterABI.json");
+/// ApeSwap on BNB Smart Chain.
+pub struct ApeSwap;
 
 impl ApeSwap {
-    pub fn new(
-        provider: Arc<NonceManagerMiddleware<SignerMiddleware<Provider<Http>, LocalWallet>>>,
-        router_address: Address,
-    ) -> Self {
-        Self {
-            base_dex: BaseDex::new(provider, router_address),
-        }
-    }
-}
-
-#[async_trait]
-impl Dex for ApeSwap {
-    async fn initialize(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
-        self.base_dex
-            .create_router_contract(APESWAP_ROUTER_ABI_JSON)
-            .await
-    }
-
-    fn clone_box(&self) -> Box<dyn Dex + Send + Sync> {
-        Box::new(self.clone())
-    }
-
-    fn name(&self) -> &str {
-        "ApeSwap"
-    }
-
-    fn router_contract(
-        &self,
-    ) -> Result<
-        &Contract<NonceManagerMiddleware<SignerMiddleware<Provider<Http>, LocalWallet>>>,
-        Box<dyn Error + Send + Sync + 'static>,
-    > {
-        self.base_dex.router_contract()
+    pub fn config(router_address: Address) -> UniswapV2Config {
+        UniswapV2Config::new(
+            "ApeSwap",
+            BlockChain::BscChain { chain_id: 56 },
+            router_address,
+            20,
+        )
+        .with_router_abi(APESWAP_ROUTER_ABI_JSON)
     }
 
-    fn provider(
-        &self,
-    ) -> Arc<NonceManagerMiddleware<SignerMiddleware<Provider<Http>, LocalWallet>>> {
-        self.base_dex.provider()
+    pub fn dex<M: Middleware>(provider: Arc<M>, router_address: Address) -> UniswapV2Dex<M> {
+        UniswapV2Dex::new(provider, Self::config(router_address))
     }
 
-    fn router_address(&self) -> Address {
-        self.base_dex.router_address()
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new<M: Middleware>(provider: Arc<M>, router_address: Address) -> UniswapV2Dex<M> {
+        Self::dex(provider, router_address)
     }
 }
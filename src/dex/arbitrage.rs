@@ -0,0 +1,499 @@
+// arbitrage.rs
+
+use super::amm::{get_amounts_out, to_f64, Hop};
+use super::dex::{pool_fee_bps, Dex, MAX_CONCURRENT_FETCHES};
+use crate::token::{Token, TokenAmount};
+use ethers::{
+    providers::Middleware,
+    types::{Address, U256},
+};
+use futures::stream::{self, StreamExt};
+use std::collections::HashSet;
+use std::error::Error;
+use std::sync::Arc;
+
+/// One directed pool in the token graph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct GraphEdge {
+    from: usize,
+    to: usize,
+    dex_index: usize,
+    pair_address: Address,
+    hop: Hop,
+}
+
+/// One swap of an arbitrage cycle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CycleLeg {
+    pub token_in: Address,
+    pub token_out: Address,
+    /// Index into `TokenGraph::dexes`.
+    pub dex_index: usize,
+    pub dex_name: String,
+    pub pair_address: Address,
+    pub hop: Hop,
+}
+
+/// A closed route that returns more of its first token than it spends.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArbitrageCycle {
+    /// Token symbols along the cycle; the last one is the first again.
+    pub symbols: Vec<String>,
+    pub legs: Vec<CycleLeg>,
+    /// Input that maximizes the profit at the snapshot's reserves.
+    pub amount_in: TokenAmount,
+    pub amount_out: TokenAmount,
+    /// `amount_out - amount_in`, after every pool's fee.
+    pub profit: TokenAmount,
+    /// `profit` relative to `amount_in`.
+    pub return_rate: f64,
+}
+
+/// Pools between a set of tokens across several DEXes of one chain, read once
+/// and searched offline for profitable cycles.
+pub struct TokenGraph<M: Middleware + 'static> {
+    tokens: Vec<Arc<Box<dyn Token<M>>>>,
+    dexes: Vec<Box<dyn Dex<M>>>,
+    edges: Vec<GraphEdge>,
+}
+
+impl<M: Middleware + 'static> Clone for TokenGraph<M> {
+    fn clone(&self) -> Self {
+        Self {
+            tokens: self.tokens.clone(),
+            dexes: self.dexes.clone(),
+            edges: self.edges.clone(),
+        }
+    }
+}
+
+impl<M: Middleware + 'static> TokenGraph<M> {
+    /// Reads every pair between `tokens` on every DEX, at most
+    /// `MAX_CONCURRENT_FETCHES` pools at a time. Pairs that do not exist or
+    /// cannot be read are left out. `trader` selects the fee on forks that
+    /// charge per trader.
+    pub async fn build(
+        tokens: Vec<Arc<Box<dyn Token<M>>>>,
+        dexes: Vec<Box<dyn Dex<M>>>,
+        trader: Address,
+    ) -> Result<Self, Box<dyn Error + Send + Sync + 'static>> {
+        let mut pairs = vec![];
+        for dex_index in 0..dexes.len() {
+            for a in 0..tokens.len() {
+                for b in a + 1..tokens.len() {
+                    pairs.push((dex_index, a, b));
+                }
+            }
+        }
+
+        let fetched: Vec<_> = stream::iter(pairs.iter().map(|(dex_index, a, b)| {
+            fetch_edges(
+                dexes[*dex_index].as_ref(),
+                *dex_index,
+                (*a, tokens[*a].address()),
+                (*b, tokens[*b].address()),
+                trader,
+            )
+        }))
+        .buffered(MAX_CONCURRENT_FETCHES)
+        .collect()
+        .await;
+
+        let mut edges = vec![];
+        for ((dex_index, a, b), fetched) in pairs.iter().zip(fetched) {
+            match fetched {
+                Ok(pair_edges) => edges.extend(pair_edges),
+                Err(err) => log::debug!(
+                    "{}: cannot read the {}/{} pool: {}",
+                    dexes[*dex_index].name(),
+                    tokens[*a].symbol_name(),
+                    tokens[*b].symbol_name(),
+                    err
+                ),
+            }
+        }
+
+        Ok(Self {
+            tokens,
+            dexes,
+            edges,
+        })
+    }
+
+    pub fn tokens(&self) -> &[Arc<Box<dyn Token<M>>>] {
+        &self.tokens
+    }
+
+    pub fn dexes(&self) -> &[Box<dyn Dex<M>>] {
+        &self.dexes
+    }
+
+    /// Number of directed pools in the graph.
+    pub fn edge_count(&self) -> usize {
+        self.edges.len()
+    }
+
+    /// Finds profitable cycles with a Bellman-Ford negative-cycle search on
+    /// `-ln(price after fee)`, then sizes each one exactly. Best return first.
+    pub fn find_cycles(&self) -> Vec<ArbitrageCycle> {
+        let mut seen = HashSet::new();
+        let mut cycles: Vec<ArbitrageCycle> = self
+            .negative_cycles()
+            .into_iter()
+            .filter(|edge_indices| seen.insert(edge_indices.clone()))
+            .filter_map(|edge_indices| self.size_cycle(&edge_indices))
+            .collect();
+        cycles.sort_by(|a, b| b.return_rate.total_cmp(&a.return_rate));
+        cycles
+    }
+
+    /// Edge indices of each negative cycle, rotated to start at the smallest
+    /// index so the same cycle is reported once.
+    fn negative_cycles(&self) -> Vec<Vec<usize>> {
+        let weights: Vec<f64> = self
+            .edges
+            .iter()
+            .map(|edge| -log_price(&edge.hop))
+            .collect();
+
+        // Every token starts at distance zero, as if linked to a virtual source
+        let token_count = self.tokens.len();
+        let mut distance = vec![0.0; token_count];
+        let mut predecessor: Vec<Option<usize>> = vec![None; token_count];
+        for _ in 0..token_count {
+            let mut relaxed = false;
+            for (edge_index, edge) in self.edges.iter().enumerate() {
+                let candidate = distance[edge.from] + weights[edge_index];
+                if candidate < distance[edge.to] - RELAXATION_EPSILON {
+                    distance[edge.to] = candidate;
+                    predecessor[edge.to] = Some(edge_index);
+                    relaxed = true;
+                }
+            }
+            if !relaxed {
+                return vec![];
+            }
+        }
+
+        let mut cycles = vec![];
+        for (edge_index, edge) in self.edges.iter().enumerate() {
+            if distance[edge.from] + weights[edge_index] >= distance[edge.to] - RELAXATION_EPSILON {
+                continue;
+            }
+
+            // Walking back `token_count` steps is guaranteed to land on the cycle
+            let mut token = edge.to;
+            for _ in 0..token_count {
+                match predecessor[token] {
+                    Some(edge_index) => token = self.edges[edge_index].from,
+                    None => break,
+                }
+            }
+
+            let mut cycle = vec![];
+            let mut current = token;
+            while let Some(edge_index) = predecessor[current] {
+                cycle.push(edge_index);
+                current = self.edges[edge_index].from;
+                if current == token || cycle.len() > token_count {
+                    break;
+                }
+            }
+            if current != token {
+                continue;
+            }
+
+            cycle.reverse();
+            let start = (0..cycle.len()).min_by_key(|i| cycle[*i]).unwrap_or(0);
+            cycle.rotate_left(start);
+            cycles.push(cycle);
+        }
+        cycles
+    }
+
+    /// Sizes a cycle with the closed-form optimum of its chained
+    /// constant-product curves and checks the result with the exact math.
+    fn size_cycle(&self, edge_indices: &[usize]) -> Option<ArbitrageCycle> {
+        let hops: Vec<Hop> = edge_indices
+            .iter()
+            .map(|edge_index| self.edges[*edge_index].hop)
+            .collect();
+
+        // The route behaves like out = a * x / (1 + c * x); profit peaks where
+        // its slope drops to one
+        let mut a = 1.0;
+        let mut c = 0.0;
+        for hop in &hops {
+            let gamma = 1.0 - hop.fee_bps as f64 / 10_000.0;
+            let reserve_in = to_f64(hop.reserve_in);
+            c += gamma * a / reserve_in;
+            a *= gamma * to_f64(hop.reserve_out) / reserve_in;
+        }
+        if a <= 1.0 || c <= 0.0 {
+            return None;
+        }
+        let optimal = (a.sqrt() - 1.0) / c;
+        let amount_in = U256::from_dec_str(&format!("{:.0}", optimal)).ok()?;
+        if amount_in.is_zero() {
+            return None;
+        }
+
+        let amounts = get_amounts_out(amount_in, &hops).ok()?;
+        let amount_out = amounts[hops.len()];
+        if amount_out <= amount_in {
+            return None;
+        }
+
+        let first_edge = &self.edges[edge_indices[0]];
+        let decimals = self.tokens[first_edge.from].required_decimals().ok()?;
+        let mut symbols = vec![self.tokens[first_edge.from].symbol_name().to_owned()];
+        let legs = edge_indices
+            .iter()
+            .map(|edge_index| {
+                let edge = &self.edges[*edge_index];
+                symbols.push(self.tokens[edge.to].symbol_name().to_owned());
+                CycleLeg {
+                    token_in: self.tokens[edge.from].address(),
+                    token_out: self.tokens[edge.to].address(),
+                    dex_index: edge.dex_index,
+                    dex_name: self.dexes[edge.dex_index].name().to_owned(),
+                    pair_address: edge.pair_address,
+                    hop: edge.hop,
+                }
+            })
+            .collect();
+
+        let profit = amount_out - amount_in;
+        Some(ArbitrageCycle {
+            symbols,
+            legs,
+            amount_in: TokenAmount::new(amount_in, decimals),
+            amount_out: TokenAmount::new(amount_out, decimals),
+            profit: TokenAmount::new(profit, decimals),
+            return_rate: to_f64(profit) / to_f64(amount_in),
+        })
+    }
+}
+
+/// Ignores improvements too small to be anything but float noise.
+const RELAXATION_EPSILON: f64 = 1e-12;
+
+fn log_price(hop: &Hop) -> f64 {
+    let gamma = 1.0 - hop.fee_bps as f64 / 10_000.0;
+    (gamma * to_f64(hop.reserve_out) / to_f64(hop.reserve_in)).ln()
+}
+
+/// Both directions of the `a`/`b` pool on `dex`, or none when there is no
+/// pool or it is empty.
+async fn fetch_edges<M: Middleware + 'static, D: Dex<M> + ?Sized>(
+    dex: &D,
+    dex_index: usize,
+    (a, a_address): (usize, Address),
+    (b, b_address): (usize, Address),
+    trader: Address,
+) -> Result<Vec<GraphEdge>, Box<dyn Error + Send + Sync + 'static>> {
+    let Some(pair_address) = dex.get_pair(a_address, b_address).await? else {
+        return Ok(vec![]);
+    };
+    let pool = dex.pool_snapshot(pair_address).await?;
+    if pool.reserve0.is_zero() || pool.reserve1.is_zero() {
+        return Ok(vec![]);
+    }
+
+    let mut edges = Vec::with_capacity(2);
+    for (from, to, token_in, token_out) in
+        [(a, b, a_address, b_address), (b, a, b_address, a_address)]
+    {
+        let fee_bps = pool_fee_bps(dex, pair_address, token_in, token_out, trader).await;
+        if let Some(hop) = pool.hop(token_in, fee_bps) {
+            edges.push(GraphEdge {
+                from,
+                to,
+                dex_index,
+                pair_address,
+                hop,
+            });
+        }
+    }
+    Ok(edges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dex::uniswap_v2::{UniswapV2Config, UniswapV2Dex};
+    use crate::token::token::BlockChain;
+    use crate::token::BscToken;
+    use ethers::providers::{MockProvider, Provider};
+
+    type TestProvider = Provider<MockProvider>;
+
+    const BSC: BlockChain = BlockChain::BscChain { chain_id: 56 };
+
+    fn ether(amount: u64) -> U256 {
+        U256::exp10(18) * amount
+    }
+
+    /// Both directions of a pool between `a` and `b` on `dex_index`.
+    fn pool(
+        dex_index: usize,
+        (a, reserve_a): (usize, u64),
+        (b, reserve_b): (usize, u64),
+    ) -> [GraphEdge; 2] {
+        let pair_address = Address::from_low_u64_be((100 + dex_index * 10 + a + b) as u64);
+        [
+            GraphEdge {
+                from: a,
+                to: b,
+                dex_index,
+                pair_address,
+                hop: Hop::new(ether(reserve_a), ether(reserve_b), 30),
+            },
+            GraphEdge {
+                from: b,
+                to: a,
+                dex_index,
+                pair_address,
+                hop: Hop::new(ether(reserve_b), ether(reserve_a), 30),
+            },
+        ]
+    }
+
+    fn graph(
+        symbols: &[&str],
+        dex_count: usize,
+        pools: Vec<[GraphEdge; 2]>,
+    ) -> TokenGraph<TestProvider> {
+        let provider = Arc::new(Provider::mocked().0);
+        let tokens = symbols
+            .iter()
+            .enumerate()
+            .map(|(index, symbol)| {
+                let token: Box<dyn Token<TestProvider>> = Box::new(BscToken::new(
+                    BSC,
+                    provider.clone(),
+                    Address::from_low_u64_be(index as u64 + 1),
+                    symbol.to_string(),
+                    Some(18),
+                ));
+                Arc::new(token)
+            })
+            .collect();
+        let dexes = (0..dex_count)
+            .map(|index| {
+                let config =
+                    UniswapV2Config::new(format!("Dex{}", index), BSC, Address::zero(), 30);
+                Box::new(UniswapV2Dex::new(provider.clone(), config)) as Box<dyn Dex<TestProvider>>
+            })
+            .collect();
+        TokenGraph {
+            tokens,
+            dexes,
+            edges: pools.into_iter().flatten().collect(),
+        }
+    }
+
+    fn triangle(ca_reserves: (u64, u64)) -> TokenGraph<TestProvider> {
+        graph(
+            &["A", "B", "C"],
+            1,
+            vec![
+                pool(0, (0, 1_000), (1, 1_000)),
+                pool(0, (1, 1_000), (2, 1_000)),
+                pool(0, (2, ca_reserves.0), (0, ca_reserves.1)),
+            ],
+        )
+    }
+
+    fn profit(hops: &[Hop], amount_in: U256) -> i128 {
+        let amount_out = get_amounts_out(amount_in, hops).unwrap()[hops.len()];
+        (amount_out.as_u128() as i128) - (amount_in.as_u128() as i128)
+    }
+
+    #[test]
+    fn finds_a_profitable_triangle_once() {
+        // C buys 10% more A than the other pools imply
+        let graph = triangle((1_000, 1_100));
+        let cycles = graph.find_cycles();
+
+        assert_eq!(cycles.len(), 1);
+        let cycle = &cycles[0];
+        assert_eq!(cycle.legs.len(), 3);
+        assert_eq!(cycle.symbols.first(), cycle.symbols.last());
+        // Whatever the starting token, the cycle runs A -> B -> C
+        let route = cycle.symbols.join("");
+        assert!(
+            ["ABCA", "BCAB", "CABC"].contains(&route.as_str()),
+            "{}",
+            route
+        );
+        assert!(cycle.profit.raw() > U256::zero());
+        assert_eq!(
+            cycle.amount_out.raw() - cycle.amount_in.raw(),
+            cycle.profit.raw()
+        );
+        assert!(cycle.return_rate > 0.0);
+    }
+
+    #[test]
+    fn balanced_pools_have_no_cycle() {
+        assert!(triangle((1_000, 1_000)).find_cycles().is_empty());
+        // A 0.5% edge does not cover three 0.3% fees
+        assert!(triangle((1_000, 1_005)).find_cycles().is_empty());
+    }
+
+    #[test]
+    fn finds_a_cycle_across_dexes() {
+        let graph = graph(
+            &["A", "B"],
+            2,
+            vec![
+                pool(0, (0, 1_000), (1, 1_000)),
+                pool(1, (0, 1_000), (1, 1_100)),
+            ],
+        );
+        let cycles = graph.find_cycles();
+
+        assert_eq!(cycles.len(), 1);
+        let dexes: Vec<usize> = cycles[0].legs.iter().map(|leg| leg.dex_index).collect();
+        assert!(dexes.contains(&0) && dexes.contains(&1));
+    }
+
+    #[test]
+    fn negative_cycles_are_rotated_to_their_smallest_edge() {
+        let graph = triangle((1_000, 1_100));
+        let cycles = graph.negative_cycles();
+
+        assert!(!cycles.is_empty());
+        for cycle in &cycles {
+            assert_eq!(cycle[0], *cycle.iter().min().unwrap());
+        }
+        assert!(cycles.windows(2).all(|pair| pair[0] == pair[1]));
+    }
+
+    #[test]
+    fn sizes_the_cycle_at_its_closed_form_optimum() {
+        let graph = triangle((1_000, 1_100));
+        let cycle = &graph.find_cycles()[0];
+        let hops: Vec<Hop> = cycle.legs.iter().map(|leg| leg.hop).collect();
+
+        // out = a * x / (1 + c * x), so the profit peaks at x = (sqrt(a) - 1) / c
+        let gamma: f64 = 0.997;
+        let mut a = 1.0;
+        let mut c = 0.0;
+        for hop in &hops {
+            let (reserve_in, reserve_out) = (to_f64(hop.reserve_in), to_f64(hop.reserve_out));
+            c += gamma * a / reserve_in;
+            a *= gamma * reserve_out / reserve_in;
+        }
+        let optimal = (a.sqrt() - 1.0) / c;
+        let amount_in = cycle.amount_in.raw();
+        assert!((to_f64(amount_in) - optimal).abs() / optimal < 1e-9);
+
+        // The exact math agrees that nearby sizes earn less
+        let best = profit(&hops, amount_in);
+        assert_eq!(best as u128, cycle.profit.raw().as_u128());
+        assert!(best >= profit(&hops, amount_in * 99 / 100));
+        assert!(best >= profit(&hops, amount_in * 101 / 100));
+    }
+}
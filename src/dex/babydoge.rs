@@ -1,63 +1,33 @@
-// pancakeswap.rs
+// babydoge.rs
 
-use super::dex::BaseDex;
-use super::Dex;
-use async_trait::async_trait;
-use ethers::{prelude::*, types::Address};
-use std::error::Error;
+use super::uniswap_v2::{FeeModel, UniswapV2Config, UniswapV2Dex};
+use crate::token::token::BlockChain;
+use ethers::{providers::Middleware, types::Address};
 use std::sync::Arc;
 
-#[derive(Debug, Clone)]
-pub struct BabyDoge {
-    base_dex: BaseDex,
-}
+static BABYDOGE_ROUTER_ABI_JSON: &[u8] = include_bytes!("../../resources/BabyDogeRouterABI.json");
 
-static BABYDOGE_ROUTER_ABI_JSON: &'static [u8] =
-    include_bytes!("../../resources/BabyDogeRouterABI.json");
+/// BabyDogeSwap on BNB Smart Chain. The router quotes the fee per trade.
+pub struct BabyDoge;
 
 impl BabyDoge {
-    pub fn new(
-        provider: Arc<NonceManagerMiddleware<SignerMiddleware<Provider<Http>, LocalWallet>>>,
-        router_address: Address,
-    ) -> Self {
-        Self {
-            base_dex: BaseDex::new(provider, router_address),
-        }
-    }
-}
-
-#[async_trait]
-impl Dex for BabyDoge {
-    async fn initialize(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
-        self.base_dex
-            .create_router_contract(BABYDOGE_ROUTER_ABI_JSON)
-            .await
-    }
-
-    fn clone_box(&self) -> Box<dyn Dex + Send + Sync> {
-        Box::new(self.clone())
-    }
-
-    fn name(&self) -> &str {
-        "BabyDoge"
-    }
-
-    fn router_contract(
-        &self,
-    ) -> Result<
-        &Contract<NonceManagerMiddleware<SignerMiddleware<Provider<Http>, LocalWallet>>>,
-        Box<dyn Error + Send + Sync + 'static>,
-    > {
-        self.base_dex.router_contract()
+    pub fn config(router_address: Address) -> UniswapV2Config {
+        UniswapV2Config::new(
+            "BabyDoge",
+            BlockChain::BscChain { chain_id: 56 },
+            router_address,
+            30,
+        )
+        .with_fee_model(FeeModel::RouterTransactionFee)
+        .with_router_abi(BABYDOGE_ROUTER_ABI_JSON)
     }
 
-    fn provider(
-        &self,
-    ) -> Arc<NonceManagerMiddleware<SignerMiddleware<Provider<Http>, LocalWallet>>> {
-        self.base_dex.provider()
+    pub fn dex<M: Middleware>(provider: Arc<M>, router_address: Address) -> UniswapV2Dex<M> {
+        UniswapV2Dex::new(provider, Self::config(router_address))
     }
 
-    fn router_address(&self) -> Address {
-        self.base_dex.router_address()
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new<M: Middleware>(provider: Arc<M>, router_address: Address) -> UniswapV2Dex<M> {
+        Self::dex(provider, router_address)
     }
 }
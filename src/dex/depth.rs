@@ -0,0 +1,253 @@
+// depth.rs
+
+use super::amm::{get_amounts_out, to_f64, AmmError, Hop};
+use crate::token::TokenAmount;
+use ethers::types::U256;
+
+/// One rung of a depth ladder.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DepthPoint {
+    pub amount_in: TokenAmount,
+    pub amount_out: TokenAmount,
+    /// Output received per unit of input.
+    pub execution_price: f64,
+    /// How far the execution price falls short of the fee-adjusted mid
+    /// price, as a fraction (0.01 = 1%). The pool fee itself is excluded.
+    pub price_impact: f64,
+}
+
+/// Execution price and price impact across trade sizes for one route,
+/// computed from a single snapshot of its pools.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DepthCurve {
+    /// Output per unit of input at the current reserves, before fees.
+    pub mid_price: f64,
+    /// Mid price after every hop has taken its fee; the best price any trade
+    /// can get.
+    pub fee_adjusted_mid_price: f64,
+    pub points: Vec<DepthPoint>,
+}
+
+impl DepthCurve {
+    /// Builds the curve for `sizes` of the route's input token. `decimals` are
+    /// the (input, output) token decimals. Zero sizes and sizes in other
+    /// decimals are left out of `points`.
+    pub fn new(hops: &[Hop], sizes: &[TokenAmount], decimals: (u8, u8)) -> Result<Self, AmmError> {
+        let (input_decimals, output_decimals) = decimals;
+        let decimal_factor = 10f64.powi(input_decimals as i32 - output_decimals as i32);
+
+        let (raw_mid_price, raw_fee_adjusted_mid_price) = mid_prices(hops)?;
+        let mid_price = raw_mid_price * decimal_factor;
+        let fee_adjusted_mid_price = raw_fee_adjusted_mid_price * decimal_factor;
+
+        let mut points = Vec::with_capacity(sizes.len());
+        for amount_in in sizes {
+            if amount_in.decimals() != input_decimals {
+                log::warn!(
+                    "Skipping depth size {} with {} decimals, the input token has {}",
+                    amount_in,
+                    amount_in.decimals(),
+                    input_decimals
+                );
+                continue;
+            }
+            if amount_in.is_zero() {
+                continue;
+            }
+
+            let amounts = get_amounts_out(amount_in.raw(), hops)?;
+            let amount_out = TokenAmount::new(amounts[hops.len()], output_decimals);
+            let execution_price = amount_out.to_f64() / amount_in.to_f64();
+            points.push(DepthPoint {
+                amount_in: *amount_in,
+                amount_out,
+                execution_price,
+                price_impact: (1.0 - execution_price / fee_adjusted_mid_price).max(0.0),
+            });
+        }
+
+        Ok(Self {
+            mid_price,
+            fee_adjusted_mid_price,
+            points,
+        })
+    }
+}
+
+/// `steps` sizes starting at `smallest`, each `multiplier` times the last.
+pub fn size_ladder(smallest: TokenAmount, multiplier: u32, steps: usize) -> Vec<TokenAmount> {
+    let mut sizes = Vec::with_capacity(steps);
+    let mut size = Some(smallest);
+    while let Some(current) = size {
+        if sizes.len() == steps {
+            break;
+        }
+        sizes.push(current);
+        size = current
+            .raw()
+            .checked_mul(U256::from(multiplier))
+            .map(|raw| TokenAmount::new(raw, current.decimals()));
+    }
+    sizes
+}
+
+/// Largest input along `hops` whose price impact stays within
+/// `max_price_impact` (0.01 = 1%), found by bisection over the same math the
+/// router's `getAmountsOut` uses. Zero when no trade fits the budget. A
+/// budget outside `(0, 1)` is rejected: at 100% impact any size qualifies.
+pub fn max_amount_in(hops: &[Hop], max_price_impact: f64) -> Result<U256, AmmError> {
+    if !(max_price_impact > 0.0 && max_price_impact < 1.0) {
+        return Err(AmmError::InvalidPriceImpact);
+    }
+    let (_, fee_adjusted_mid_price) = mid_prices(hops)?;
+    let within_budget = |amount_in: U256| match get_amounts_out(amount_in, hops) {
+        Ok(amounts) => {
+            let execution_price = to_f64(amounts[hops.len()]) / to_f64(amount_in);
+            1.0 - execution_price / fee_adjusted_mid_price <= max_price_impact
+        }
+        Err(_) => false,
+    };
+
+    // Start from the first pool's depth and double until the budget breaks
+    let mut low = U256::zero();
+    let mut high = hops[0].reserve_in;
+    while within_budget(high) {
+        low = high;
+        high = match high.checked_mul(U256::from(2)) {
+            Some(doubled) => doubled,
+            None => return Ok(low),
+        };
+    }
+
+    while high - low > U256::one() {
+        let middle = low + (high - low) / 2;
+        if within_budget(middle) {
+            low = middle;
+        } else {
+            high = middle;
+        }
+    }
+    Ok(low)
+}
+
+/// Mid price of the route in raw units, before and after fees.
+fn mid_prices(hops: &[Hop]) -> Result<(f64, f64), AmmError> {
+    if hops.is_empty() {
+        return Err(AmmError::InsufficientLiquidity);
+    }
+
+    let mut mid_price = 1.0;
+    let mut fee_adjusted_mid_price = 1.0;
+    for hop in hops {
+        if hop.reserve_in.is_zero() || hop.reserve_out.is_zero() {
+            return Err(AmmError::InsufficientLiquidity);
+        }
+        let hop_price = to_f64(hop.reserve_out) / to_f64(hop.reserve_in);
+        mid_price *= hop_price;
+        fee_adjusted_mid_price *= hop_price * (1.0 - hop.fee_bps as f64 / 10_000.0);
+    }
+    Ok((mid_price, fee_adjusted_mid_price))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool() -> Hop {
+        let ether = U256::exp10(18);
+        Hop::new(ether * 1_000, ether * 2_000, 30)
+    }
+
+    fn ether(amount: u64) -> TokenAmount {
+        TokenAmount::new(U256::exp10(18) * amount, 18)
+    }
+
+    #[test]
+    fn size_ladder_multiplies_each_step() {
+        assert_eq!(
+            size_ladder(ether(1), 10, 3),
+            vec![ether(1), ether(10), ether(100)]
+        );
+        assert!(size_ladder(ether(1), 10, 0).is_empty());
+    }
+
+    #[test]
+    fn size_ladder_stops_before_overflow() {
+        let sizes = size_ladder(TokenAmount::new(U256::MAX / 3, 18), 2, 5);
+        assert_eq!(sizes.len(), 2);
+    }
+
+    #[test]
+    fn depth_curve_prices_each_size() {
+        let curve = DepthCurve::new(&[pool()], &[ether(1), ether(10)], (18, 18)).unwrap();
+        assert!((curve.mid_price - 2.0).abs() < 1e-12);
+        assert!((curve.fee_adjusted_mid_price - 1.994).abs() < 1e-12);
+
+        assert_eq!(curve.points.len(), 2);
+        let small = curve.points[0];
+        assert_eq!(
+            small.amount_out.raw(),
+            pool().amount_out(ether(1).raw()).unwrap()
+        );
+        assert!(small.execution_price < curve.fee_adjusted_mid_price);
+        // Impact on one pool is x' / (R + x') with x' = 0.997 x
+        let expected_impact = 0.997 / 1_000.997;
+        assert!((small.price_impact - expected_impact).abs() < 1e-9);
+        assert!(curve.points[1].price_impact > small.price_impact);
+    }
+
+    #[test]
+    fn depth_curve_adjusts_for_decimals() {
+        let usdc_pool = Hop::new(U256::exp10(18) * 1_000, U256::exp10(6) * 2_000, 30);
+        let curve = DepthCurve::new(&[usdc_pool], &[ether(1)], (18, 6)).unwrap();
+        assert!((curve.mid_price - 2.0).abs() < 1e-9);
+        assert!((curve.points[0].execution_price - 1.992).abs() < 1e-3);
+    }
+
+    #[test]
+    fn depth_curve_skips_zero_and_mismatched_sizes() {
+        let sizes = [
+            TokenAmount::zero(18),
+            ether(1),
+            TokenAmount::new(U256::from(1_000_000), 6),
+        ];
+        let curve = DepthCurve::new(&[pool()], &sizes, (18, 18)).unwrap();
+        assert_eq!(curve.points.len(), 1);
+        assert_eq!(curve.points[0].amount_in, ether(1));
+    }
+
+    #[test]
+    fn depth_curve_needs_liquidity() {
+        let empty = Hop::new(U256::zero(), U256::exp10(18), 30);
+        assert_eq!(
+            DepthCurve::new(&[empty], &[ether(1)], (18, 18)),
+            Err(AmmError::InsufficientLiquidity)
+        );
+        assert_eq!(
+            DepthCurve::new(&[], &[ether(1)], (18, 18)),
+            Err(AmmError::InsufficientLiquidity)
+        );
+    }
+
+    #[test]
+    fn max_amount_in_rejects_budgets_outside_zero_to_one() {
+        for budget in [0.0, 1.0, 1.5, -0.01, f64::NAN, f64::INFINITY] {
+            assert_eq!(
+                max_amount_in(&[pool()], budget),
+                Err(AmmError::InvalidPriceImpact),
+                "budget {}",
+                budget
+            );
+        }
+    }
+
+    #[test]
+    fn max_amount_in_matches_closed_form_for_one_pool() {
+        // Impact on one pool is x' / (R + x') with x' = 0.997 x, so a 1%
+        // budget allows x = 0.01 R / 0.99 / 0.997
+        let amount_in = max_amount_in(&[pool()], 0.01).unwrap();
+        let expected = 0.01 * 1_000.0 / 0.99 / 0.997;
+        let actual = TokenAmount::new(amount_in, 18).to_f64();
+        assert!((actual - expected).abs() / expected < 1e-9, "{}", actual);
+    }
+}
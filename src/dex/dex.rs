@@ -1,62 +1,54 @@
 // dex.rs
 
-use crate::token::Token;
+use super::amm::Hop;
+use super::depth::{max_amount_in, DepthCurve};
+use super::error::{is_excessive_input, is_insufficient_output, revert_reason, DexError};
+use super::pool::{factory_contract, pair_contract, PoolSnapshot};
+use super::swap_options::SwapOptions;
+use super::swap_path::SwapPath;
+use super::swap_receipt::{decode_swap_receipt, received_amount, SwapFill};
+use super::uniswap_v2::FeeModel;
+use crate::chain::ChainInfo;
+use crate::fee_policy::FeePolicy;
+use crate::signer::Signing;
+use crate::token::token::BlockChain;
+use crate::token::{Token, TokenAmount};
 use async_trait::async_trait;
-use ethers::prelude::LocalWallet;
-use ethers::utils::parse_units;
 use ethers::{
-    abi::Abi,
+    abi::{Abi, Detokenize},
+    contract::decode_function_data,
     prelude::*,
     types::{Address, U256},
 };
 use std::{error::Error, sync::Arc};
 
-use ethers::core::types::Log;
+/// Pool or quote reads in flight at once when fanning out over many pairs,
+/// to stay under provider rate limits. Reading one pool takes `getPair`, four
+/// snapshot calls and up to two fee reads.
+pub(crate) const MAX_CONCURRENT_FETCHES: usize = 8;
 
-use anyhow::Error as AnyhowError;
-
-fn parse_swap_log(log: &Log) -> Result<(f64, f64, f64, f64), AnyhowError> {
-    // Check if this log has the correct number of topics and the data
-    if log.topics.len() < 2 || log.data.is_empty() {
-        return Err(AnyhowError::msg(
-            "Log does not have enough topics/data for parsing swap",
-        ));
-    }
-
-    // Convert topics to U256
-    let amount0_in = U256::from(log.topics[0].as_fixed_bytes());
-    let amount1_in = U256::from(log.topics[1].as_fixed_bytes());
-
-    // Convert log.data to byte slice and then slice it
-    let data = log.data.as_ref();
-    let amount0_out = U256::from_big_endian(&data[0..32]);
-    let amount1_out = U256::from_big_endian(&data[32..64]);
-
-    // Convert U256 amounts to f64
-    let amount0_in = amount0_in.low_u64() as f64;
-    let amount1_in = amount1_in.low_u64() as f64;
-    let amount0_out = amount0_out.low_u64() as f64;
-    let amount1_out = amount1_out.low_u64() as f64;
-
-    Ok((amount0_in, amount1_in, amount0_out, amount1_out))
+#[derive(Debug)]
+pub struct BaseDex<M: Middleware> {
+    pub provider: Arc<M>,
+    pub router_address: Address,
+    router_contract: Option<Contract<M>>,
 }
 
-#[derive(Debug, Clone)]
-pub struct BaseDex {
-    pub provider: Arc<NonceManagerMiddleware<SignerMiddleware<Provider<Http>, LocalWallet>>>,
-    pub router_address: Address,
-    router_contract:
-        Option<Contract<NonceManagerMiddleware<SignerMiddleware<Provider<Http>, LocalWallet>>>>,
+impl<M: Middleware> Clone for BaseDex<M> {
+    fn clone(&self) -> Self {
+        Self {
+            provider: self.provider.clone(),
+            router_address: self.router_address,
+            router_contract: self.router_contract.clone(),
+        }
+    }
 }
 
-impl BaseDex {
-    pub fn new(
-        provider: Arc<NonceManagerMiddleware<SignerMiddleware<Provider<Http>, LocalWallet>>>,
-        router_address: Address,
-    ) -> Self {
+impl<M: Middleware> BaseDex<M> {
+    pub fn new(provider: Arc<M>, router_address: Address) -> Self {
         Self {
-            provider: provider,
-            router_address: router_address,
+            provider,
+            router_address,
             router_contract: None,
         }
     }
@@ -74,9 +66,7 @@ impl BaseDex {
         Ok(())
     }
 
-    pub fn provider(
-        &self,
-    ) -> Arc<NonceManagerMiddleware<SignerMiddleware<Provider<Http>, LocalWallet>>> {
+    pub fn provider(&self) -> Arc<M> {
         self.provider.clone()
     }
 
@@ -84,30 +74,32 @@ impl BaseDex {
         self.router_address
     }
 
-    pub fn router_contract(
-        &self,
-    ) -> Result<
-        &Contract<NonceManagerMiddleware<SignerMiddleware<Provider<Http>, LocalWallet>>>,
-        Box<dyn Error + Send + Sync + 'static>,
-    > {
+    pub fn router_contract(&self) -> Result<&Contract<M>, Box<dyn Error + Send + Sync + 'static>> {
         match &self.router_contract {
             Some(contract) => Ok(contract),
-            None => Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::Other,
+            None => Err(Box::new(std::io::Error::other(
                 "Router contract not created",
             ))),
         }
     }
 }
 
-#[derive(Clone)]
-pub struct TokenPair {
-    input_token: Arc<Box<dyn Token>>,
-    output_token: Arc<Box<dyn Token>>,
+pub struct TokenPair<M: Middleware> {
+    input_token: Arc<Box<dyn Token<M>>>,
+    output_token: Arc<Box<dyn Token<M>>>,
+}
+
+impl<M: Middleware> Clone for TokenPair<M> {
+    fn clone(&self) -> Self {
+        Self {
+            input_token: self.input_token.clone(),
+            output_token: self.output_token.clone(),
+        }
+    }
 }
 
-impl TokenPair {
-    pub fn new(input_token: Arc<Box<dyn Token>>, output_token: Arc<Box<dyn Token>>) -> Self {
+impl<M: Middleware> TokenPair<M> {
+    pub fn new(input_token: Arc<Box<dyn Token<M>>>, output_token: Arc<Box<dyn Token<M>>>) -> Self {
         TokenPair {
             input_token,
             output_token,
@@ -120,56 +112,505 @@ impl TokenPair {
             output_token: self.output_token,
         }
     }
+
+    pub fn input_token(&self) -> &Arc<Box<dyn Token<M>>> {
+        &self.input_token
+    }
+
+    pub fn output_token(&self) -> &Arc<Box<dyn Token<M>>> {
+        &self.output_token
+    }
+}
+
+fn path_amounts<M: Middleware>(
+    path: &SwapPath<M>,
+    amounts: Vec<U256>,
+) -> Result<Vec<TokenAmount>, Box<dyn Error + Send + Sync + 'static>> {
+    if amounts.len() != path.tokens().len() {
+        return Err(Box::new(std::io::Error::other(format!(
+            "Router returned {} amounts for path {}",
+            amounts.len(),
+            path.symbol_names()
+        ))));
+    }
+
+    path.tokens()
+        .iter()
+        .zip(amounts)
+        .map(|(token, amount)| Ok(TokenAmount::new(amount, token.required_decimals()?)))
+        .collect()
+}
+
+/// Initial amount of a quote along a path.
+fn first_amount(
+    amounts: &[TokenAmount],
+) -> Result<TokenAmount, Box<dyn Error + Send + Sync + 'static>> {
+    amounts.first().copied().ok_or_else(no_amounts)
+}
+
+/// Reads the pair factory the router was deployed with.
+pub(crate) async fn router_factory<M: Middleware + 'static>(
+    router_contract: &Contract<M>,
+) -> Result<Address, Box<dyn Error + Send + Sync + 'static>> {
+    let factory_address: Address = router_contract.method("factory", ())?.call().await?;
+    Ok(factory_address)
+}
+
+/// Reads the router's `WETH()`, which is what its native swaps require, and
+/// warns when the chain registry lists a different wrapper.
+pub(crate) async fn router_wrapped_native<M: Middleware + 'static, D: Dex<M> + ?Sized>(
+    dex: &D,
+) -> Result<Address, Box<dyn Error + Send + Sync + 'static>> {
+    let router_contract = dex.router_contract()?;
+    let name = router_method_name(router_contract, "WETH")?;
+    let address: Address = router_contract.method(&name, ())?.call().await?;
+    if let Some(info) = dex.chain_info() {
+        if info.wrapped_native_address != address {
+            log::warn!(
+                "{}: router wraps native currency through {:?}, the {} registry lists {:?}",
+                dex.name(),
+                address,
+                info.name,
+                info.wrapped_native_address
+            );
+        }
+    }
+    Ok(address)
+}
+
+/// Final amount of a quote along a path.
+fn last_amount(
+    amounts: &[TokenAmount],
+) -> Result<TokenAmount, Box<dyn Error + Send + Sync + 'static>> {
+    amounts.last().copied().ok_or_else(no_amounts)
+}
+
+fn no_amounts() -> Box<dyn Error + Send + Sync + 'static> {
+    Box::new(std::io::Error::other("Router returned no amounts"))
+}
+
+fn swap_deadline(deadline_secs: u64) -> Result<U256, Box<dyn Error + Send + Sync + 'static>> {
+    Ok(U256::from(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs()
+            + deadline_secs,
+    ))
+}
+
+/// Resolves a router function by its canonical Uniswap-V2 name, falling back
+/// to the BNB-flavoured spelling used by forks such as BakerySwap.
+fn router_method_name<M: Middleware>(
+    router_contract: &Contract<M>,
+    name: &str,
+) -> Result<String, DexError> {
+    if router_contract.abi().function(name).is_ok() {
+        return Ok(name.to_owned());
+    }
+    let bnb_name = name.replace("ETH", "BNB");
+    if router_contract.abi().function(&bnb_name).is_ok() {
+        return Ok(bnb_name);
+    }
+    Err(DexError::Unsupported(name.to_owned()))
+}
+
+/// Maps a failed router call to `revert_error` when it carries a revert
+/// reason.
+fn router_call_error<M: Middleware + 'static>(
+    err: ContractError<M>,
+    revert_error: impl FnOnce(String) -> DexError,
+) -> Box<dyn Error + Send + Sync + 'static> {
+    match revert_reason(&err) {
+        Some(reason) => Box::new(revert_error(reason)),
+        None => Box::new(err),
+    }
+}
+
+/// Executes a router swap call with `eth_call` from `from` without sending it.
+/// Returns the router's per-hop amounts, or `None` for the fee-on-transfer
+/// variants, which return nothing. A revert reason is mapped through
+/// `revert_error`.
+async fn simulate_router_call<M: Middleware + 'static>(
+    method_call: &ContractCall<M, Vec<U256>>,
+    from: Address,
+    revert_error: impl FnOnce(String) -> DexError + Send,
+) -> Result<Option<Vec<U256>>, Box<dyn Error + Send + Sync + 'static>> {
+    let method_call = method_call.clone().from(from);
+
+    let bytes = match method_call.call_raw_bytes().await {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            let err = match RpcError::as_error_response(&err).and_then(JsonRpcError::as_revert_data)
+            {
+                Some(data) => ContractError::<M>::Revert(data),
+                None => ContractError::ProviderError { e: err },
+            };
+            return Err(router_call_error(err, revert_error));
+        }
+    };
+
+    if method_call.function.outputs.is_empty() {
+        return Ok(None);
+    }
+    let amounts: Vec<U256> = decode_function_data(&method_call.function, &bytes, false)?;
+    Ok(Some(amounts))
+}
+
+/// Prices a router call with `fee_policy`, sends it from the wallet and waits
+/// for a successful receipt once `block_chain`'s confirmations are reached.
+/// A revert reason, whether found while estimating gas or sending, is mapped
+/// through `revert_error`.
+async fn send_router_call<M: Signing + 'static, D: Detokenize + Send + Sync>(
+    method_call: ContractCall<M, D>,
+    wallet_and_provider: &M,
+    fee_policy: &FeePolicy,
+    block_chain: &BlockChain,
+    revert_error: impl FnOnce(String) -> DexError + Send,
+) -> Result<TransactionReceipt, Box<dyn Error + Send + Sync + 'static>> {
+    let mut method_call = method_call.from(wallet_and_provider.signer_address());
+    fee_policy
+        .apply_fees(wallet_and_provider, &mut method_call.tx)
+        .await?;
+
+    let estimated_gas = match method_call.estimate_gas().await {
+        Ok(estimated_gas) => estimated_gas,
+        Err(err) => return Err(router_call_error(err, revert_error)),
+    };
+    method_call.tx.set_gas(fee_policy.gas_limit(estimated_gas)?);
+
+    let pending_transaction = match method_call.send().await {
+        Ok(pending) => pending,
+        Err(err) => return Err(router_call_error(err, revert_error)),
+    };
+
+    let transaction_receipt = block_chain.wait_for(pending_transaction).await?;
+
+    let transaction_receipt = match transaction_receipt {
+        Some(receipt) => receipt,
+        None => {
+            return Err(Box::new(std::io::Error::other(
+                "Transaction receipt is none",
+            )))
+        }
+    };
+
+    if transaction_receipt.status != Some(1.into()) {
+        return Err(Box::new(std::io::Error::other("Router transaction failed")));
+    }
+
+    Ok(transaction_receipt)
+}
+
+/// Sends a router swap call, waits for a successful receipt and decodes the
+/// amounts moved. A revert reason is mapped through `revert_error`.
+///
+/// `accounts` are the (sender, recipient) used for `Transfer` accounting; for
+/// native legs this is the router itself, which wraps and unwraps the coin.
+/// With `options.simulate` the call is first simulated from the wallet and
+/// only sent when the simulation succeeds.
+async fn execute_swap<M: Signing + 'static>(
+    method_call: ContractCall<M, Vec<U256>>,
+    path: &SwapPath<M>,
+    accounts: (Address, Address),
+    revert_error: impl Fn(String) -> DexError + Send + Sync,
+    wallet_and_provider: &M,
+    options: &SwapOptions,
+) -> Result<SwapFill, Box<dyn Error + Send + Sync + 'static>> {
+    let input_decimals = path.input_token().required_decimals()?;
+    let output_decimals = path.output_token().required_decimals()?;
+
+    if options.simulate {
+        let from = wallet_and_provider.signer_address();
+        let amounts = simulate_router_call(&method_call, from, &revert_error).await?;
+        if let Some(expected_out) = amounts.as_deref().and_then(<[U256]>::last) {
+            log::debug!(
+                "Simulated swap along {}: expected output {}",
+                path.symbol_names(),
+                TokenAmount::new(*expected_out, output_decimals)
+            );
+        }
+    }
+
+    let fee_policy = options
+        .fee_policy
+        .as_ref()
+        .unwrap_or_else(|| path.input_token().fee_policy());
+    let transaction_receipt = send_router_call(
+        method_call,
+        wallet_and_provider,
+        fee_policy,
+        &path.input_token().block_chain(),
+        revert_error,
+    )
+    .await?;
+
+    let fill = decode_swap_receipt(
+        &transaction_receipt,
+        (path.input_token().address(), input_decimals),
+        (path.output_token().address(), output_decimals),
+        accounts.0,
+        accounts.1,
+    )?;
+
+    log::debug!(
+        "Swapped {}({}) for {}({}) in {} hops",
+        fill.amount_in,
+        path.input_token().symbol_name(),
+        fill.amount_out,
+        path.output_token().symbol_name(),
+        fill.hops.len()
+    );
+
+    Ok(fill)
+}
+
+/// Builds the `swapExactTokensForTokens` call for `path`, or its
+/// fee-on-transfer variant.
+fn exact_input_call<M: Middleware + 'static>(
+    router_contract: &Contract<M>,
+    path: &SwapPath<M>,
+    amount_in: TokenAmount,
+    min_output: TokenAmount,
+    options: &SwapOptions,
+    fee_on_transfer: bool,
+) -> Result<ContractCall<M, Vec<U256>>, Box<dyn Error + Send + Sync + 'static>> {
+    let name = if fee_on_transfer {
+        router_method_name(
+            router_contract,
+            "swapExactTokensForTokensSupportingFeeOnTransferTokens",
+        )?
+    } else {
+        "swapExactTokensForTokens".to_owned()
+    };
+
+    Ok(router_contract.method::<_, Vec<U256>>(
+        &name,
+        (
+            amount_in.raw(),
+            min_output.raw(),
+            path.addresses(),
+            options.recipient,
+            swap_deadline(options.deadline_secs)?,
+        ),
+    )?)
+}
+
+fn min_output_error(min_output: TokenAmount) -> impl Fn(String) -> DexError + Send + Sync {
+    move |reason| {
+        if is_insufficient_output(&reason) {
+            DexError::InsufficientOutput { min_output, reason }
+        } else {
+            DexError::Reverted(reason)
+        }
+    }
+}
+
+fn max_input_error(max_input: TokenAmount) -> impl Fn(String) -> DexError + Send + Sync {
+    move |reason| {
+        if is_excessive_input(&reason) {
+            DexError::ExcessiveInput { max_input, reason }
+        } else {
+            DexError::Reverted(reason)
+        }
+    }
+}
+
+fn uses_fee_on_transfer<M: Middleware>(path: &SwapPath<M>, options: &SwapOptions) -> bool {
+    options.fee_on_transfer || path.has_fee_on_transfer_token()
+}
+
+/// Exact-output swaps have no fee-on-transfer variant on V2 routers.
+fn reject_fee_on_transfer<M: Middleware>(
+    path: &SwapPath<M>,
+    options: &SwapOptions,
+    function: &str,
+) -> Result<(), DexError> {
+    if uses_fee_on_transfer(path, options) {
+        return Err(DexError::Unsupported(format!(
+            "{} with fee-on-transfer tokens",
+            function
+        )));
+    }
+    Ok(())
+}
+
+/// Replaces the decoded output with the recipient's balance change, which is
+/// the only reliable figure once a token has taken its transfer fee.
+async fn apply_balance_delta<M: Middleware + 'static>(
+    mut fill: SwapFill,
+    path: &SwapPath<M>,
+    recipient: Address,
+    balance_before: U256,
+) -> Result<SwapFill, Box<dyn Error + Send + Sync + 'static>> {
+    let balance_after = path.output_token().balance_of(recipient).await?;
+    fill.amount_out = TokenAmount::new(
+        balance_after.saturating_sub(balance_before),
+        fill.amount_out.decimals(),
+    );
+    Ok(fill)
+}
+
+/// Tops up the router's allowance on the path's input token when the swap
+/// options carry an approval manager.
+async fn ensure_router_allowance<M: Signing + 'static, D: Dex<M> + ?Sized>(
+    dex: &D,
+    path: &SwapPath<M>,
+    amount_in: TokenAmount,
+    options: &SwapOptions,
+) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
+    if let Some(approval_manager) = &options.approval_manager {
+        approval_manager
+            .ensure_allowance(
+                path.input_token().as_ref().as_ref(),
+                dex.router_address(),
+                amount_in.raw(),
+            )
+            .await?;
+    }
+    Ok(())
+}
+
+fn record_router_spent<M: Middleware + 'static, D: Dex<M> + ?Sized>(
+    dex: &D,
+    path: &SwapPath<M>,
+    fill: &SwapFill,
+    options: &SwapOptions,
+) {
+    if let Some(approval_manager) = &options.approval_manager {
+        approval_manager.record_spent(
+            path.input_token().address(),
+            dex.router_address(),
+            fill.amount_in.raw(),
+        );
+    }
+}
+
+async fn check_native_leg<M: Middleware + 'static, D: Dex<M> + ?Sized>(
+    dex: &D,
+    token_address: Address,
+) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
+    let wrapped_native = dex.wrapped_native_address().await?;
+    if token_address != wrapped_native {
+        return Err(Box::new(std::io::Error::other(format!(
+            "{}: native swap path must use wrapped native token {:?}",
+            dex.name(),
+            wrapped_native
+        ))));
+    }
+    Ok(())
+}
+
+async fn slippage_min_output<M: Middleware + 'static, D: Dex<M> + ?Sized>(
+    dex: &D,
+    path: &SwapPath<M>,
+    amount_in: TokenAmount,
+    options: &SwapOptions,
+) -> Result<TokenAmount, Box<dyn Error + Send + Sync + 'static>> {
+    if options.slippage.needs_quote() {
+        let amounts_out = dex.get_amounts_out(path, amount_in).await?;
+        Ok(options.slippage.min_output(last_amount(&amounts_out)?))
+    } else {
+        let output_decimals = path.output_token().required_decimals()?;
+        Ok(options
+            .slippage
+            .min_output(TokenAmount::zero(output_decimals)))
+    }
+}
+
+async fn slippage_max_input<M: Middleware + 'static, D: Dex<M> + ?Sized>(
+    dex: &D,
+    path: &SwapPath<M>,
+    amount_out: TokenAmount,
+    options: &SwapOptions,
+) -> Result<TokenAmount, Box<dyn Error + Send + Sync + 'static>> {
+    let amounts_in = dex.get_amounts_in(path, amount_out).await?;
+    Ok(options.slippage.max_input(first_amount(&amounts_in)?))
+}
+
+/// Reads the fee a pool charges according to the DEX's fee model. Falls back
+/// to the configured default when the fork does not expose it or the read
+/// fails.
+pub(crate) async fn pool_fee_bps<M: Middleware + 'static, D: Dex<M> + ?Sized>(
+    dex: &D,
+    pair_address: Address,
+    token_in: Address,
+    token_out: Address,
+    trader: Address,
+) -> u32 {
+    let fee_bps: Result<u32, Box<dyn Error + Send + Sync + 'static>> = match dex.fee_model() {
+        FeeModel::Fixed => return dex.fee_bps(),
+        FeeModel::PairSwapFee => {
+            async {
+                let swap_fee: u32 = pair_contract(pair_address, dex.provider())
+                    .method("swapFee", ())?
+                    .call()
+                    .await?;
+                // swapFee is in tenths of a percent; a bogus value saturates and
+                // is rejected below
+                Ok(swap_fee.saturating_mul(10))
+            }
+            .await
+        }
+        FeeModel::RouterTransactionFee => {
+            async {
+                let transaction_fee: U256 = dex
+                    .router_contract()?
+                    .method("transactionFee", (token_in, token_out, trader))?
+                    .call()
+                    .await?;
+                Ok(u32::try_from(transaction_fee).unwrap_or(u32::MAX))
+            }
+            .await
+        }
+    };
+
+    match fee_bps {
+        Ok(fee_bps) if fee_bps < 10_000 => fee_bps,
+        Ok(fee_bps) => {
+            log::warn!(
+                "{}: ignoring fee of {} bps for pair {:?}",
+                dex.name(),
+                fee_bps,
+                pair_address
+            );
+            dex.fee_bps()
+        }
+        Err(err) => {
+            log::warn!(
+                "{}: failed to read fee for pair {:?}, using {} bps: {}",
+                dex.name(),
+                pair_address,
+                dex.fee_bps(),
+                err
+            );
+            dex.fee_bps()
+        }
+    }
 }
 
 #[async_trait]
-pub trait Dex: Send + Sync {
+pub trait Dex<M: Middleware + 'static>: Send + Sync {
     async fn get_token_price(
         &self,
-        token_pair: &TokenPair,
+        token_pair: &TokenPair<M>,
         amount: f64,
         use_get_amounts_in: bool,
     ) -> Result<f64, Box<dyn std::error::Error + Send + Sync + 'static>> {
-        let input_address = token_pair.input_token.address();
-        let output_address = token_pair.output_token.address();
-
-        let input_decimals = token_pair.input_token.decimals().unwrap();
-        let output_decimals = token_pair.output_token.decimals().unwrap();
-
-        let router_contract = self.router_contract().unwrap();
-
-        let mut amount_in = U256::from_dec_str(&format!(
-            "{:.0}",
-            amount * 10f64.powi(input_decimals as i32)
-        ))?;
-
-        let mut amount_out = U256::from_dec_str(&format!(
-            "{:.0}",
-            amount * 10f64.powi(output_decimals as i32)
-        ))?;
-
-        if use_get_amounts_in {
-            let amounts_in: Vec<U256> = router_contract
-                .method::<_, Vec<U256>>(
-                    "getAmountsIn",
-                    (amount_out, vec![input_address, output_address]),
-                )?
-                .call()
-                .await?;
-            amount_in = amounts_in[0];
+        let input_decimals = token_pair.input_token.required_decimals()?;
+        let output_decimals = token_pair.output_token.required_decimals()?;
+
+        let (amount_in, amount_out) = if use_get_amounts_in {
+            let amount_out = TokenAmount::from_f64(amount, output_decimals)?;
+            (
+                self.get_amount_in(token_pair, amount_out).await?,
+                amount_out,
+            )
         } else {
-            let amounts_out: Vec<U256> = router_contract
-                .method::<_, Vec<U256>>(
-                    "getAmountsOut",
-                    (amount_in, vec![input_address, output_address]),
-                )?
-                .call()
-                .await?;
-            amount_out = amounts_out[1];
-        }
+            let amount_in = TokenAmount::from_f64(amount, input_decimals)?;
+            (amount_in, self.get_amount_out(token_pair, amount_in).await?)
+        };
 
-        let price_f64 = amount_out.as_u128() as f64 / amount_in.as_u128() as f64
-            * 10f64.powi(input_decimals as i32 - output_decimals as i32);
+        let price_f64 = amount_out.to_f64() / amount_in.to_f64();
 
         log::trace!(
             "{}, Amount-in: {}({}), Amount-out: {}({}), Price: {:6.6}",
@@ -184,96 +625,739 @@ pub trait Dex: Send + Sync {
         Ok(price_f64)
     }
 
+    async fn get_amount_out(
+        &self,
+        token_pair: &TokenPair<M>,
+        amount_in: TokenAmount,
+    ) -> Result<TokenAmount, Box<dyn std::error::Error + Send + Sync + 'static>> {
+        let amounts_out = self
+            .get_amounts_out(&SwapPath::from(token_pair), amount_in)
+            .await?;
+        last_amount(&amounts_out)
+    }
+
+    async fn get_amount_in(
+        &self,
+        token_pair: &TokenPair<M>,
+        amount_out: TokenAmount,
+    ) -> Result<TokenAmount, Box<dyn std::error::Error + Send + Sync + 'static>> {
+        let amounts_in = self
+            .get_amounts_in(&SwapPath::from(token_pair), amount_out)
+            .await?;
+        first_amount(&amounts_in)
+    }
+
+    /// Quotes `amount_in` along `path`, returning the amount held after each hop
+    /// (the first element is `amount_in` itself).
+    async fn get_amounts_out(
+        &self,
+        path: &SwapPath<M>,
+        amount_in: TokenAmount,
+    ) -> Result<Vec<TokenAmount>, Box<dyn std::error::Error + Send + Sync + 'static>> {
+        let amounts_out: Vec<U256> = self
+            .router_contract()?
+            .method::<_, Vec<U256>>("getAmountsOut", (amount_in.raw(), path.addresses()))?
+            .call()
+            .await?;
+
+        path_amounts(path, amounts_out)
+    }
+
+    /// Quotes the input needed along `path` to receive `amount_out`, returning
+    /// the amount required before each hop (the last element is `amount_out`).
+    async fn get_amounts_in(
+        &self,
+        path: &SwapPath<M>,
+        amount_out: TokenAmount,
+    ) -> Result<Vec<TokenAmount>, Box<dyn std::error::Error + Send + Sync + 'static>> {
+        let amounts_in: Vec<U256> = self
+            .router_contract()?
+            .method::<_, Vec<U256>>("getAmountsIn", (amount_out.raw(), path.addresses()))?
+            .call()
+            .await?;
+
+        path_amounts(path, amounts_in)
+    }
+
     async fn swap_token(
         &self,
-        token_pair: &TokenPair,
+        token_pair: &TokenPair<M>,
         amount: f64,
-        wallet_and_provider: Arc<
-            NonceManagerMiddleware<SignerMiddleware<Provider<Http>, LocalWallet>>,
-        >,
+        wallet_and_provider: Arc<M>,
         address: Address,
         deadline_secs: u64,
-    ) -> Result<f64, Box<dyn std::error::Error + Send + Sync + 'static>> {
-        let input_address = token_pair.input_token.address();
-        let output_address = token_pair.output_token.address();
-
-        let input_decimals = token_pair.input_token.decimals().unwrap();
-        let amount_in = U256::from_dec_str(&format!(
-            "{:.0}",
-            amount * 10f64.powi(input_decimals as i32)
-        ))?;
-
-        let router_contract = self.router_contract().unwrap();
-
-        let deadline = U256::from(
-            std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)?
-                .as_secs()
-                + deadline_secs,
+    ) -> Result<f64, Box<dyn std::error::Error + Send + Sync + 'static>>
+    where
+        M: Signing,
+    {
+        let input_decimals = token_pair.input_token.required_decimals()?;
+        let amount_in = TokenAmount::from_f64(amount, input_decimals)?;
+
+        let output_amount = self
+            .swap_token_amount(
+                token_pair,
+                amount_in,
+                wallet_and_provider,
+                address,
+                deadline_secs,
+            )
+            .await?;
+
+        Ok(output_amount.to_f64())
+    }
+
+    async fn swap_token_amount(
+        &self,
+        token_pair: &TokenPair<M>,
+        amount_in: TokenAmount,
+        wallet_and_provider: Arc<M>,
+        address: Address,
+        deadline_secs: u64,
+    ) -> Result<TokenAmount, Box<dyn std::error::Error + Send + Sync + 'static>>
+    where
+        M: Signing,
+    {
+        self.swap_path(
+            &SwapPath::from(token_pair),
+            amount_in,
+            wallet_and_provider,
+            address,
+            deadline_secs,
+        )
+        .await
+    }
+
+    async fn swap_path(
+        &self,
+        path: &SwapPath<M>,
+        amount_in: TokenAmount,
+        wallet_and_provider: Arc<M>,
+        address: Address,
+        deadline_secs: u64,
+    ) -> Result<TokenAmount, Box<dyn std::error::Error + Send + Sync + 'static>>
+    where
+        M: Signing,
+    {
+        let fill = self
+            .swap_path_with_options(
+                path,
+                amount_in,
+                wallet_and_provider,
+                &SwapOptions::new(address, deadline_secs),
+            )
+            .await?;
+        Ok(fill.amount_out)
+    }
+
+    /// Swaps `amount_in` along `path`, submitting the `amountOutMin` derived
+    /// from `options.slippage`. Returns `DexError::InsufficientOutput` when the
+    /// router rejects the bound, otherwise the amounts decoded from the receipt.
+    async fn swap_path_with_options(
+        &self,
+        path: &SwapPath<M>,
+        amount_in: TokenAmount,
+        wallet_and_provider: Arc<M>,
+        options: &SwapOptions,
+    ) -> Result<SwapFill, Box<dyn std::error::Error + Send + Sync + 'static>>
+    where
+        M: Signing,
+    {
+        let min_output = slippage_min_output(self, path, amount_in, options).await?;
+        ensure_router_allowance(self, path, amount_in, options).await?;
+
+        log::debug!(
+            "{}, Swap {} along {} with minimum output {}",
+            self.name(),
+            amount_in,
+            path.symbol_names(),
+            min_output
         );
 
+        let fee_on_transfer = uses_fee_on_transfer(path, options);
+        let connected_contract = self.router_contract()?.connect(wallet_and_provider.clone());
+        let method_call = exact_input_call(
+            &connected_contract,
+            path,
+            amount_in,
+            min_output,
+            options,
+            fee_on_transfer,
+        )?;
+
+        let balance_before = if fee_on_transfer {
+            Some(path.output_token().balance_of(options.recipient).await?)
+        } else {
+            None
+        };
+
+        let fill = execute_swap(
+            method_call,
+            path,
+            (wallet_and_provider.signer_address(), options.recipient),
+            min_output_error(min_output),
+            wallet_and_provider.as_ref(),
+            options,
+        )
+        .await?;
+        record_router_spent(self, path, &fill, options);
+
+        match balance_before {
+            Some(balance_before) => {
+                apply_balance_delta(fill, path, options.recipient, balance_before).await
+            }
+            None => Ok(fill),
+        }
+    }
+
+    /// Executes the router call `swap_path_with_options` would send with
+    /// `eth_call` from `from`, without sending a transaction. Returns the
+    /// expected output, `None` for fee-on-transfer swaps whose router
+    /// functions report no amounts, or the decoded revert reason as a
+    /// `DexError`.
+    async fn simulate_swap_path(
+        &self,
+        path: &SwapPath<M>,
+        amount_in: TokenAmount,
+        from: Address,
+        options: &SwapOptions,
+    ) -> Result<Option<TokenAmount>, Box<dyn std::error::Error + Send + Sync + 'static>> {
+        let min_output = slippage_min_output(self, path, amount_in, options).await?;
+        let method_call = exact_input_call(
+            self.router_contract()?,
+            path,
+            amount_in,
+            min_output,
+            options,
+            uses_fee_on_transfer(path, options),
+        )?;
+
+        let amounts =
+            simulate_router_call(&method_call, from, min_output_error(min_output)).await?;
+        match amounts {
+            Some(amounts) => Ok(Some(path_amounts(path, amounts)?[path.hops()])),
+            None => Ok(None),
+        }
+    }
+
+    /// Buys exactly `amount_out` of the last token in `path` through
+    /// `swapTokensForExactTokens`. The `amountInMax` bound is quoted with
+    /// `getAmountsIn` and widened by `options.slippage`.
+    async fn swap_path_for_exact_output(
+        &self,
+        path: &SwapPath<M>,
+        amount_out: TokenAmount,
+        wallet_and_provider: Arc<M>,
+        options: &SwapOptions,
+    ) -> Result<SwapFill, Box<dyn std::error::Error + Send + Sync + 'static>>
+    where
+        M: Signing,
+    {
+        reject_fee_on_transfer(path, options, "swapTokensForExactTokens")?;
+        let max_input = slippage_max_input(self, path, amount_out, options).await?;
+        ensure_router_allowance(self, path, max_input, options).await?;
+
+        log::debug!(
+            "{}, Buy {} along {} with maximum input {}",
+            self.name(),
+            amount_out,
+            path.symbol_names(),
+            max_input
+        );
+
+        let connected_contract = self.router_contract()?.connect(wallet_and_provider.clone());
+
+        let method_call = connected_contract.method::<_, Vec<U256>>(
+            "swapTokensForExactTokens",
+            (
+                amount_out.raw(),
+                max_input.raw(),
+                path.addresses(),
+                options.recipient,
+                swap_deadline(options.deadline_secs)?,
+            ),
+        )?;
+
+        let fill = execute_swap(
+            method_call,
+            path,
+            (wallet_and_provider.signer_address(), options.recipient),
+            max_input_error(max_input),
+            wallet_and_provider.as_ref(),
+            options,
+        )
+        .await?;
+        record_router_spent(self, path, &fill, options);
+        Ok(fill)
+    }
+
+    /// Address of the wrapped native token (WBNB/WMATIC/WETH) the router
+    /// swaps native currency through, from the router's `WETH()`.
+    async fn wrapped_native_address(
+        &self,
+    ) -> Result<Address, Box<dyn std::error::Error + Send + Sync + 'static>> {
+        router_wrapped_native(self).await
+    }
+
+    /// Sells exactly `amount_in` of native currency along `path`, which must
+    /// start with the wrapped native token.
+    async fn swap_exact_native_for_tokens(
+        &self,
+        path: &SwapPath<M>,
+        amount_in: TokenAmount,
+        wallet_and_provider: Arc<M>,
+        options: &SwapOptions,
+    ) -> Result<SwapFill, Box<dyn std::error::Error + Send + Sync + 'static>>
+    where
+        M: Signing,
+    {
+        check_native_leg(self, path.input_token().address()).await?;
+        let min_output = slippage_min_output(self, path, amount_in, options).await?;
+
+        let router_contract = self.router_contract()?;
+        let fee_on_transfer = uses_fee_on_transfer(path, options);
+        let name = router_method_name(
+            router_contract,
+            if fee_on_transfer {
+                "swapExactETHForTokensSupportingFeeOnTransferTokens"
+            } else {
+                "swapExactETHForTokens"
+            },
+        )?;
+        let connected_contract = router_contract.connect(wallet_and_provider.clone());
+
+        let method_call = connected_contract
+            .method::<_, Vec<U256>>(
+                &name,
+                (
+                    min_output.raw(),
+                    path.addresses(),
+                    options.recipient,
+                    swap_deadline(options.deadline_secs)?,
+                ),
+            )?
+            .value(amount_in.raw());
+
+        let balance_before = if fee_on_transfer {
+            Some(path.output_token().balance_of(options.recipient).await?)
+        } else {
+            None
+        };
+
+        let fill = execute_swap(
+            method_call,
+            path,
+            (self.router_address(), options.recipient),
+            min_output_error(min_output),
+            wallet_and_provider.as_ref(),
+            options,
+        )
+        .await?;
+
+        match balance_before {
+            Some(balance_before) => {
+                apply_balance_delta(fill, path, options.recipient, balance_before).await
+            }
+            None => Ok(fill),
+        }
+    }
+
+    /// Sells exactly `amount_in` along `path`, which must end with the wrapped
+    /// native token, and pays out unwrapped native currency.
+    async fn swap_exact_tokens_for_native(
+        &self,
+        path: &SwapPath<M>,
+        amount_in: TokenAmount,
+        wallet_and_provider: Arc<M>,
+        options: &SwapOptions,
+    ) -> Result<SwapFill, Box<dyn std::error::Error + Send + Sync + 'static>>
+    where
+        M: Signing,
+    {
+        check_native_leg(self, path.output_token().address()).await?;
+        let min_output = slippage_min_output(self, path, amount_in, options).await?;
+        ensure_router_allowance(self, path, amount_in, options).await?;
+
+        let router_contract = self.router_contract()?;
+        // The router unwraps exactly the WETH it received, so the decoded
+        // amount stays accurate for fee-on-transfer inputs
+        let name = router_method_name(
+            router_contract,
+            if uses_fee_on_transfer(path, options) {
+                "swapExactTokensForETHSupportingFeeOnTransferTokens"
+            } else {
+                "swapExactTokensForETH"
+            },
+        )?;
         let connected_contract = router_contract.connect(wallet_and_provider.clone());
 
-        let method_call = connected_contract.method::<_, bool>(
-            "swapExactTokensForTokens",
+        let method_call = connected_contract.method::<_, Vec<U256>>(
+            &name,
             (
-                amount_in,
-                U256::zero(),
-                vec![input_address, output_address],
-                address,
-                deadline,
+                amount_in.raw(),
+                min_output.raw(),
+                path.addresses(),
+                options.recipient,
+                swap_deadline(options.deadline_secs)?,
             ),
         )?;
 
-        let swap_transaction = method_call.send().await?;
+        let fill = execute_swap(
+            method_call,
+            path,
+            (wallet_and_provider.signer_address(), self.router_address()),
+            min_output_error(min_output),
+            wallet_and_provider.as_ref(),
+            options,
+        )
+        .await?;
+        record_router_spent(self, path, &fill, options);
+        Ok(fill)
+    }
 
-        let transaction_receipt = swap_transaction.confirmations(1).await?; // wait for 1 confirmation
+    /// Buys exactly `amount_out` along `path`, which must start with the
+    /// wrapped native token. The router refunds unused native currency.
+    async fn swap_native_for_exact_tokens(
+        &self,
+        path: &SwapPath<M>,
+        amount_out: TokenAmount,
+        wallet_and_provider: Arc<M>,
+        options: &SwapOptions,
+    ) -> Result<SwapFill, Box<dyn std::error::Error + Send + Sync + 'static>>
+    where
+        M: Signing,
+    {
+        check_native_leg(self, path.input_token().address()).await?;
+        reject_fee_on_transfer(path, options, "swapETHForExactTokens")?;
+        let max_input = slippage_max_input(self, path, amount_out, options).await?;
 
-        let transaction_receipt = match transaction_receipt {
-            Some(receipt) => receipt,
-            None => {
-                return Err(Box::new(std::io::Error::new(
-                    std::io::ErrorKind::Other,
-                    "Transaction receipt is none",
-                )))
+        let router_contract = self.router_contract()?;
+        let name = router_method_name(router_contract, "swapETHForExactTokens")?;
+        let connected_contract = router_contract.connect(wallet_and_provider.clone());
+
+        let method_call = connected_contract
+            .method::<_, Vec<U256>>(
+                &name,
+                (
+                    amount_out.raw(),
+                    path.addresses(),
+                    options.recipient,
+                    swap_deadline(options.deadline_secs)?,
+                ),
+            )?
+            .value(max_input.raw());
+
+        execute_swap(
+            method_call,
+            path,
+            (self.router_address(), options.recipient),
+            max_input_error(max_input),
+            wallet_and_provider.as_ref(),
+            options,
+        )
+        .await
+    }
+
+    /// Buys exactly `amount_out` of native currency along `path`, which must
+    /// end with the wrapped native token.
+    async fn swap_tokens_for_exact_native(
+        &self,
+        path: &SwapPath<M>,
+        amount_out: TokenAmount,
+        wallet_and_provider: Arc<M>,
+        options: &SwapOptions,
+    ) -> Result<SwapFill, Box<dyn std::error::Error + Send + Sync + 'static>>
+    where
+        M: Signing,
+    {
+        check_native_leg(self, path.output_token().address()).await?;
+        reject_fee_on_transfer(path, options, "swapTokensForExactETH")?;
+        let max_input = slippage_max_input(self, path, amount_out, options).await?;
+        ensure_router_allowance(self, path, max_input, options).await?;
+
+        let router_contract = self.router_contract()?;
+        let name = router_method_name(router_contract, "swapTokensForExactETH")?;
+        let connected_contract = router_contract.connect(wallet_and_provider.clone());
+
+        let method_call = connected_contract.method::<_, Vec<U256>>(
+            &name,
+            (
+                amount_out.raw(),
+                max_input.raw(),
+                path.addresses(),
+                options.recipient,
+                swap_deadline(options.deadline_secs)?,
+            ),
+        )?;
+
+        let fill = execute_swap(
+            method_call,
+            path,
+            (wallet_and_provider.signer_address(), self.router_address()),
+            max_input_error(max_input),
+            wallet_and_provider.as_ref(),
+            options,
+        )
+        .await?;
+        record_router_spent(self, path, &fill, options);
+        Ok(fill)
+    }
+
+    /// Burns `liquidity` of the pair's LP token for the underlying tokens.
+    /// When the LP token supports EIP-2612 the approval is signed off-chain and
+    /// passed to `removeLiquidityWithPermit`; otherwise the router is approved
+    /// on-chain first.
+    #[allow(clippy::too_many_arguments)]
+    async fn remove_liquidity(
+        &self,
+        token_pair: &TokenPair<M>,
+        liquidity_token: &dyn Token<M>,
+        liquidity: U256,
+        min_amounts: (TokenAmount, TokenAmount),
+        wallet_and_provider: Arc<M>,
+        options: &SwapOptions,
+    ) -> Result<(TokenAmount, TokenAmount), Box<dyn std::error::Error + Send + Sync + 'static>>
+    where
+        M: Signing,
+    {
+        let token_a = token_pair.input_token();
+        let token_b = token_pair.output_token();
+        let owner = wallet_and_provider.signer_address();
+        let deadline = swap_deadline(options.deadline_secs)?;
+        let connected_contract = self.router_contract()?.connect(wallet_and_provider.clone());
+        let fee_policy = options
+            .fee_policy
+            .as_ref()
+            .unwrap_or_else(|| token_a.fee_policy());
+
+        let transaction_receipt = if liquidity_token.supports_permit(owner).await {
+            let permit = liquidity_token
+                .sign_permit(self.router_address(), liquidity, deadline)
+                .await?;
+
+            let method_call = connected_contract.method::<_, (U256, U256)>(
+                "removeLiquidityWithPermit",
+                (
+                    token_a.address(),
+                    token_b.address(),
+                    liquidity,
+                    min_amounts.0.raw(),
+                    min_amounts.1.raw(),
+                    options.recipient,
+                    deadline,
+                    false,
+                    permit.v,
+                    permit.r,
+                    permit.s,
+                ),
+            )?;
+            send_router_call(
+                method_call,
+                wallet_and_provider.as_ref(),
+                fee_policy,
+                &token_a.block_chain(),
+                DexError::Reverted,
+            )
+            .await?
+        } else {
+            match &options.approval_manager {
+                Some(approval_manager) => {
+                    approval_manager
+                        .ensure_allowance(liquidity_token, self.router_address(), liquidity)
+                        .await?
+                }
+                None => {
+                    liquidity_token
+                        .approve_and_wait(self.router_address(), liquidity)
+                        .await?
+                }
             }
+
+            let method_call = connected_contract.method::<_, (U256, U256)>(
+                "removeLiquidity",
+                (
+                    token_a.address(),
+                    token_b.address(),
+                    liquidity,
+                    min_amounts.0.raw(),
+                    min_amounts.1.raw(),
+                    options.recipient,
+                    deadline,
+                ),
+            )?;
+            send_router_call(
+                method_call,
+                wallet_and_provider.as_ref(),
+                fee_policy,
+                &token_a.block_chain(),
+                DexError::Reverted,
+            )
+            .await?
         };
 
-        if transaction_receipt.status != Some(1.into()) {
-            return Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                "Token swap transaction failed",
-            )));
+        Ok((
+            TokenAmount::new(
+                received_amount(&transaction_receipt, token_a.address(), options.recipient)?,
+                token_a.required_decimals()?,
+            ),
+            TokenAmount::new(
+                received_amount(&transaction_receipt, token_b.address(), options.recipient)?,
+                token_b.required_decimals()?,
+            ),
+        ))
+    }
+
+    /// Whether the router ABI has the `...SupportingFeeOnTransferTokens`
+    /// swap functions (MeshSwap, for example, does not).
+    fn supports_fee_on_transfer(&self) -> bool {
+        match self.router_contract() {
+            Ok(router_contract) => router_method_name(
+                router_contract,
+                "swapExactTokensForTokensSupportingFeeOnTransferTokens",
+            )
+            .is_ok(),
+            Err(_) => false,
         }
+    }
 
-        // Parse the Swap event logs to get the output amount
-        let logs = transaction_receipt.logs;
+    /// Address of the pair factory, from the configuration or the router's
+    /// `factory()`.
+    async fn factory(&self) -> Result<Address, Box<dyn std::error::Error + Send + Sync + 'static>> {
+        if let Some(factory_address) = self.factory_address() {
+            return Ok(factory_address);
+        }
+        router_factory(self.router_contract()?).await
+    }
+
+    /// Pair contract of the two tokens, `None` when the factory has not
+    /// created one.
+    async fn get_pair(
+        &self,
+        token_a: Address,
+        token_b: Address,
+    ) -> Result<Option<Address>, Box<dyn std::error::Error + Send + Sync + 'static>> {
+        let factory_contract = factory_contract(self.factory().await?, self.provider());
+        let pair_address: Address = factory_contract
+            .method("getPair", (token_a, token_b))?
+            .call()
+            .await?;
+        Ok((!pair_address.is_zero()).then_some(pair_address))
+    }
+
+    async fn pool_snapshot(
+        &self,
+        pair_address: Address,
+    ) -> Result<PoolSnapshot, Box<dyn std::error::Error + Send + Sync + 'static>> {
+        PoolSnapshot::fetch(self.provider(), pair_address).await
+    }
 
-        let mut output_amount: Option<U256> = None;
+    /// Snapshot of the pool trading `token_pair`, `None` when there is no pair.
+    async fn get_pool(
+        &self,
+        token_pair: &TokenPair<M>,
+    ) -> Result<Option<PoolSnapshot>, Box<dyn std::error::Error + Send + Sync + 'static>> {
+        let pair_address = self
+            .get_pair(
+                token_pair.input_token.address(),
+                token_pair.output_token.address(),
+            )
+            .await?;
+        match pair_address {
+            Some(pair_address) => Ok(Some(self.pool_snapshot(pair_address).await?)),
+            None => Ok(None),
+        }
+    }
 
-        for log in &logs {
-            if log.address == output_address {
-                let (_amount0_in, _amount1_in, amount0_out, amount1_out) = parse_swap_log(&log)?;
-                output_amount = Some(parse_units(&(amount0_out + amount1_out), 18)?.into());
-                break;
+    /// Effective swap fee in basis points for `trader` selling `token_in` for
+    /// `token_out`, read on-chain where the fork exposes it.
+    async fn pair_fee_bps(
+        &self,
+        token_in: Address,
+        token_out: Address,
+        trader: Address,
+    ) -> Result<u32, Box<dyn std::error::Error + Send + Sync + 'static>> {
+        if self.fee_model() == FeeModel::Fixed {
+            return Ok(self.fee_bps());
+        }
+        match self.get_pair(token_in, token_out).await? {
+            Some(pair_address) => {
+                Ok(pool_fee_bps(self, pair_address, token_in, token_out, trader).await)
             }
+            None => Ok(self.fee_bps()),
         }
+    }
 
-        let output_amount = output_amount.ok_or_else(|| {
-            Box::new(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                "Output amount not found in transaction logs",
-            ))
-        })?;
+    /// Reads the pools along `path` once so the route can be quoted locally
+    /// with `amm::get_amounts_out`/`get_amounts_in`, each hop at the fee
+    /// `trader` pays on that pair.
+    async fn fetch_hops(
+        &self,
+        path: &SwapPath<M>,
+        trader: Address,
+    ) -> Result<Vec<Hop>, Box<dyn std::error::Error + Send + Sync + 'static>> {
+        let addresses = path.addresses();
+        let mut hops = Vec::with_capacity(path.hops());
+        for pair in addresses.windows(2) {
+            let pair_address = self.get_pair(pair[0], pair[1]).await?.ok_or_else(|| {
+                Box::new(std::io::Error::other(format!(
+                    "{}: no pair for {:?} and {:?}",
+                    self.name(),
+                    pair[0],
+                    pair[1]
+                )))
+            })?;
+            let pool = self.pool_snapshot(pair_address).await?;
+            let fee_bps = pool_fee_bps(self, pair_address, pair[0], pair[1], trader).await;
+            let hop = pool.hop(pair[0], fee_bps).ok_or_else(|| {
+                Box::new(std::io::Error::other(format!(
+                    "{}: pair {:?} does not hold {:?}",
+                    self.name(),
+                    pair_address,
+                    pair[0]
+                )))
+            })?;
+            hops.push(hop);
+        }
+        Ok(hops)
+    }
 
-        let output_decimals = token_pair.output_token.decimals().unwrap();
-        let output_amount_in_token =
-            output_amount.low_u64() as f64 / 10f64.powi(output_decimals as i32);
+    /// Execution price, mid price and price impact of `token_pair` for each
+    /// of `sizes`, from a single read of the pool. `trader` selects the fee on
+    /// forks that charge per trader.
+    async fn depth_curve(
+        &self,
+        token_pair: &TokenPair<M>,
+        sizes: &[TokenAmount],
+        trader: Address,
+    ) -> Result<DepthCurve, Box<dyn std::error::Error + Send + Sync + 'static>> {
+        let hops = self.fetch_hops(&SwapPath::from(token_pair), trader).await?;
+        let decimals = (
+            token_pair.input_token.required_decimals()?,
+            token_pair.output_token.required_decimals()?,
+        );
+        Ok(DepthCurve::new(&hops, sizes, decimals)?)
+    }
 
-        Ok(output_amount_in_token)
+    /// Largest amount of the path's input token that trades within
+    /// `max_price_impact` (0.01 = 1%) at the current reserves.
+    async fn max_amount_in(
+        &self,
+        path: &SwapPath<M>,
+        max_price_impact: f64,
+        trader: Address,
+    ) -> Result<TokenAmount, Box<dyn std::error::Error + Send + Sync + 'static>> {
+        let hops = self.fetch_hops(path, trader).await?;
+        let amount_in = max_amount_in(&hops, max_price_impact)?;
+        Ok(TokenAmount::new(
+            amount_in,
+            path.input_token().required_decimals()?,
+        ))
     }
 
-    async fn has_token_pair(&self, input_token: &dyn Token, output_token: &dyn Token) -> bool {
+    async fn has_token_pair(
+        &self,
+        input_token: &dyn Token<M>,
+        output_token: &dyn Token<M>,
+    ) -> bool {
         let input_address = input_token.address();
         let output_address = output_token.address();
 
@@ -310,27 +1394,39 @@ pub trait Dex: Send + Sync {
     }
 
     async fn initialize(&mut self) -> Result<(), Box<dyn Error + Send + Sync>>;
-    fn clone_box(&self) -> Box<dyn Dex + Send + Sync>;
+    fn clone_box(&self) -> Box<dyn Dex<M> + Send + Sync>;
     fn name(&self) -> &str;
-    fn provider(
-        &self,
-    ) -> Arc<NonceManagerMiddleware<SignerMiddleware<Provider<Http>, LocalWallet>>>;
+    fn provider(&self) -> Arc<M>;
     fn router_address(&self) -> Address;
-    fn router_contract(
-        &self,
-    ) -> Result<
-        &Contract<NonceManagerMiddleware<SignerMiddleware<Provider<Http>, LocalWallet>>>,
-        Box<dyn Error + Send + Sync + 'static>,
-    >;
+    fn router_contract(&self) -> Result<&Contract<M>, Box<dyn Error + Send + Sync + 'static>>;
+    /// Registry entry of the chain the DEX is deployed on, when known.
+    fn chain_info(&self) -> Option<&'static ChainInfo> {
+        None
+    }
+
+    /// Configured address of the pair factory. When `None`, `factory()`
+    /// asks the router.
+    fn factory_address(&self) -> Option<Address> {
+        None
+    }
+
+    /// Default swap fee charged by the DEX's pools, in basis points.
+    fn fee_bps(&self) -> u32 {
+        30
+    }
+
+    fn fee_model(&self) -> FeeModel {
+        FeeModel::Fixed
+    }
 }
 
-impl Clone for Box<dyn Dex> {
-    fn clone(&self) -> Box<dyn Dex> {
+impl<M: Middleware + 'static> Clone for Box<dyn Dex<M>> {
+    fn clone(&self) -> Box<dyn Dex<M>> {
         self.clone_box()
     }
 }
 
-impl PartialEq for Box<dyn Dex> {
+impl<M: Middleware + 'static> PartialEq for Box<dyn Dex<M>> {
     fn eq(&self, other: &Self) -> bool {
         std::ptr::eq(self.as_ref(), other.as_ref())
     }
@@ -0,0 +1,71 @@
+// error.rs
+
+use crate::token::TokenAmount;
+use ethers::{contract::ContractError, providers::Middleware};
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone)]
+pub enum DexError {
+    /// The router rejected the swap because the output would fall below the
+    /// submitted `amountOutMin`.
+    InsufficientOutput {
+        min_output: TokenAmount,
+        reason: String,
+    },
+    /// The router rejected an exact-output swap because the required input
+    /// exceeds the submitted `amountInMax`.
+    ExcessiveInput {
+        max_input: TokenAmount,
+        reason: String,
+    },
+    /// The router does not implement the named function.
+    Unsupported(String),
+    /// The router call reverted for any other reason.
+    Reverted(String),
+}
+
+impl fmt::Display for DexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DexError::InsufficientOutput { min_output, reason } => {
+                write!(f, "Swap output below minimum of {}: {}", min_output, reason)
+            }
+            DexError::ExcessiveInput { max_input, reason } => {
+                write!(f, "Swap input above maximum of {}: {}", max_input, reason)
+            }
+            DexError::Unsupported(function) => {
+                write!(f, "Router does not support {}", function)
+            }
+            DexError::Reverted(reason) => write!(f, "Router call reverted: {}", reason),
+        }
+    }
+}
+
+impl Error for DexError {}
+
+/// Extracts the revert reason from a failed contract call, either from the
+/// ABI-encoded `Error(string)` payload or from the node's error message.
+pub fn revert_reason<M: Middleware>(err: &ContractError<M>) -> Option<String> {
+    if let Some(reason) = err.decode_revert::<String>() {
+        return Some(reason);
+    }
+    if let Some(data) = err.as_revert() {
+        return Some(format!("0x{}", ethers::utils::hex::encode(data)));
+    }
+
+    let message = err.to_string();
+    if message.contains("revert") {
+        Some(message)
+    } else {
+        None
+    }
+}
+
+pub(crate) fn is_insufficient_output(reason: &str) -> bool {
+    reason.contains("INSUFFICIENT_OUTPUT_AMOUNT")
+}
+
+pub(crate) fn is_excessive_input(reason: &str) -> bool {
+    reason.contains("EXCESSIVE_INPUT_AMOUNT")
+}
@@ -1,29 +1,52 @@
 // mod.rs
 
+pub mod amm;
 pub mod apeswap;
 pub mod apeswap_polygon;
+pub mod arbitrage;
 pub mod babydoge;
 pub mod bakeryswap;
 pub mod baseswap;
 pub mod biswap;
+pub mod depth;
+#[allow(clippy::module_inception)]
 pub mod dex;
 pub mod dyfn;
+pub mod error;
 pub mod meshswap;
 pub mod pancakeswap_base;
 pub mod pancakeswap_bsc;
+pub mod pool;
 pub mod quickswap;
+pub mod split_router;
+pub mod spread_scanner;
 pub mod sushiswap;
+pub mod swap_options;
+pub mod swap_path;
+pub mod swap_receipt;
+pub mod uniswap_v2;
 
+pub use amm::{AmmError, Hop};
 pub use apeswap::ApeSwap;
 pub use apeswap_polygon::ApeSwapPolygon;
+pub use arbitrage::{ArbitrageCycle, CycleLeg, TokenGraph};
 pub use babydoge::BabyDoge;
 pub use bakeryswap::BakerySwap;
 pub use baseswap::BaseSwap;
 pub use biswap::BiSwap;
+pub use depth::{DepthCurve, DepthPoint};
 pub use dex::Dex;
 pub use dyfn::Dyfn;
+pub use error::DexError;
 pub use meshswap::MeshSwap;
 pub use pancakeswap_base::PancakeSwapBase;
 pub use pancakeswap_bsc::PancakeSwapBsc;
+pub use pool::PoolSnapshot;
 pub use quickswap::QuickSwap;
+pub use split_router::{LegFill, SplitLeg, SplitPlan, SplitRouter};
+pub use spread_scanner::{SpreadOpportunity, SpreadQuery, SpreadScanner};
 pub use sushiswap::SushiSwap;
+pub use swap_options::{Slippage, SwapOptions};
+pub use swap_path::SwapPath;
+pub use swap_receipt::SwapFill;
+pub use uniswap_v2::{FeeModel, UniswapV2Config, UniswapV2Dex};
@@ -0,0 +1,102 @@
+// pool.rs
+
+use super::amm::Hop;
+use ethers::{
+    abi::Abi,
+    contract::Contract,
+    providers::Middleware,
+    types::{Address, U256},
+};
+use std::error::Error;
+use std::sync::{Arc, OnceLock};
+
+static UNISWAP_V2_FACTORY_ABI_JSON: &[u8] =
+    include_bytes!("../../resources/UniswapV2FactoryABI.json");
+static UNISWAP_V2_PAIR_ABI_JSON: &[u8] = include_bytes!("../../resources/UniswapV2PairABI.json");
+
+fn factory_abi() -> &'static Abi {
+    static ABI: OnceLock<Abi> = OnceLock::new();
+    ABI.get_or_init(|| Abi::load(UNISWAP_V2_FACTORY_ABI_JSON).unwrap())
+}
+
+fn pair_abi() -> &'static Abi {
+    static ABI: OnceLock<Abi> = OnceLock::new();
+    ABI.get_or_init(|| Abi::load(UNISWAP_V2_PAIR_ABI_JSON).unwrap())
+}
+
+pub fn factory_contract<M: Middleware>(factory_address: Address, provider: Arc<M>) -> Contract<M> {
+    Contract::new(factory_address, factory_abi().clone(), provider)
+}
+
+pub fn pair_contract<M: Middleware>(pair_address: Address, provider: Arc<M>) -> Contract<M> {
+    Contract::new(pair_address, pair_abi().clone(), provider)
+}
+
+/// State of a Uniswap-V2 pair read in one go.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolSnapshot {
+    pub pair_address: Address,
+    pub token0: Address,
+    pub token1: Address,
+    pub reserve0: U256,
+    pub reserve1: U256,
+    pub block_timestamp_last: u32,
+    /// Supply of the pair's LP token.
+    pub total_supply: U256,
+}
+
+impl PoolSnapshot {
+    pub async fn fetch<M: Middleware + 'static>(
+        provider: Arc<M>,
+        pair_address: Address,
+    ) -> Result<Self, Box<dyn Error + Send + Sync + 'static>> {
+        let pair_contract = pair_contract(pair_address, provider);
+
+        let token0: Address = pair_contract.method("token0", ())?.call().await?;
+        let token1: Address = pair_contract.method("token1", ())?.call().await?;
+        let (reserve0, reserve1, block_timestamp_last): (U256, U256, u32) =
+            pair_contract.method("getReserves", ())?.call().await?;
+        let total_supply: U256 = pair_contract.method("totalSupply", ())?.call().await?;
+
+        Ok(Self {
+            pair_address,
+            token0,
+            token1,
+            reserve0,
+            reserve1,
+            block_timestamp_last,
+            total_supply,
+        })
+    }
+
+    pub fn contains(&self, token: Address) -> bool {
+        token == self.token0 || token == self.token1
+    }
+
+    pub fn reserve_of(&self, token: Address) -> Option<U256> {
+        if token == self.token0 {
+            Some(self.reserve0)
+        } else if token == self.token1 {
+            Some(self.reserve1)
+        } else {
+            None
+        }
+    }
+
+    /// Reserves as (reserve_in, reserve_out) for a swap selling `token_in`.
+    pub fn reserves_for(&self, token_in: Address) -> Option<(U256, U256)> {
+        if token_in == self.token0 {
+            Some((self.reserve0, self.reserve1))
+        } else if token_in == self.token1 {
+            Some((self.reserve1, self.reserve0))
+        } else {
+            None
+        }
+    }
+
+    /// The pool as a swap hop selling `token_in` at `fee_bps`.
+    pub fn hop(&self, token_in: Address, fee_bps: u32) -> Option<Hop> {
+        self.reserves_for(token_in)
+            .map(|(reserve_in, reserve_out)| Hop::new(reserve_in, reserve_out, fee_bps))
+    }
+}
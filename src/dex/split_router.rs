@@ -0,0 +1,406 @@
+// split_router.rs
+
+use super::amm::{get_amounts_out, AmmError, Hop};
+use super::dex::{Dex, TokenPair, MAX_CONCURRENT_FETCHES};
+use super::swap_options::{Slippage, SwapOptions};
+use super::swap_path::SwapPath;
+use super::swap_receipt::SwapFill;
+use crate::signer::Signing;
+use crate::token::{Token, TokenAmount};
+use ethers::{
+    providers::Middleware,
+    types::{Address, U256},
+};
+use futures::stream::{self, StreamExt};
+use std::error::Error;
+use std::sync::Arc;
+
+/// Number of slices an order is cut into unless configured otherwise.
+const DEFAULT_STEPS: usize = 20;
+
+/// Part of a split order routed through one DEX.
+pub struct SplitLeg<M: Middleware> {
+    /// Index into `SplitRouter::dexes`.
+    pub dex_index: usize,
+    pub dex_name: String,
+    pub path: SwapPath<M>,
+    pub amount_in: TokenAmount,
+    pub expected_out: TokenAmount,
+}
+
+impl<M: Middleware> Clone for SplitLeg<M> {
+    fn clone(&self) -> Self {
+        Self {
+            dex_index: self.dex_index,
+            dex_name: self.dex_name.clone(),
+            path: self.path.clone(),
+            amount_in: self.amount_in,
+            expected_out: self.expected_out,
+        }
+    }
+}
+
+/// How an order is divided between DEXes and routes.
+pub struct SplitPlan<M: Middleware> {
+    pub amount_in: TokenAmount,
+    /// Sum of the legs' expected outputs.
+    pub expected_out: TokenAmount,
+    pub legs: Vec<SplitLeg<M>>,
+}
+
+impl<M: Middleware> Clone for SplitPlan<M> {
+    fn clone(&self) -> Self {
+        Self {
+            amount_in: self.amount_in,
+            expected_out: self.expected_out,
+            legs: self.legs.clone(),
+        }
+    }
+}
+
+/// Outcome of one executed leg.
+#[derive(Debug)]
+pub struct LegFill {
+    pub dex_name: String,
+    /// Symbols along the leg's path, e.g. `WETH->USDC`.
+    pub route: String,
+    pub amount_in: TokenAmount,
+    pub expected_out: TokenAmount,
+    pub fill: Result<SwapFill, Box<dyn Error + Send + Sync + 'static>>,
+}
+
+/// Splits large orders across several DEXes of one chain so that the total
+/// output is as high as the pools allow.
+pub struct SplitRouter<M: Middleware + 'static> {
+    dexes: Vec<Box<dyn Dex<M>>>,
+    intermediates: Vec<Arc<Box<dyn Token<M>>>>,
+    steps: usize,
+}
+
+impl<M: Middleware + 'static> Clone for SplitRouter<M> {
+    fn clone(&self) -> Self {
+        Self {
+            dexes: self.dexes.clone(),
+            intermediates: self.intermediates.clone(),
+            steps: self.steps,
+        }
+    }
+}
+
+impl<M: Middleware + 'static> SplitRouter<M> {
+    /// All `dexes` must be on the same chain.
+    pub fn new(dexes: Vec<Box<dyn Dex<M>>>) -> Self {
+        Self {
+            dexes,
+            intermediates: vec![],
+            steps: DEFAULT_STEPS,
+        }
+    }
+
+    /// Also consider two-hop routes through each of `intermediates` on every
+    /// DEX, e.g. the wrapped native token or a major stablecoin.
+    pub fn with_intermediates(mut self, intermediates: Vec<Arc<Box<dyn Token<M>>>>) -> Self {
+        self.intermediates = intermediates;
+        self
+    }
+
+    /// Number of equal slices the order is allocated in. More slices track
+    /// the optimum more closely at the same number of RPC calls.
+    pub fn with_steps(mut self, steps: usize) -> Self {
+        self.steps = steps.max(1);
+        self
+    }
+
+    pub fn dexes(&self) -> &[Box<dyn Dex<M>>] {
+        &self.dexes
+    }
+
+    /// Reads the pools of every candidate route once, then hands out
+    /// `amount_in` slice by slice to whichever route adds the most output
+    /// for the next slice. `trader` selects the fee on forks that charge per
+    /// trader.
+    pub async fn plan(
+        &self,
+        token_pair: &TokenPair<M>,
+        amount_in: TokenAmount,
+        trader: Address,
+    ) -> Result<SplitPlan<M>, Box<dyn Error + Send + Sync + 'static>> {
+        if amount_in.is_zero() {
+            return Err(Box::new(AmmError::InsufficientInputAmount));
+        }
+        let output_decimals = token_pair.output_token().required_decimals()?;
+        let routes = self.fetch_routes(token_pair, trader).await;
+        if routes.is_empty() {
+            return Err(Box::new(std::io::Error::other(format!(
+                "No route from {} to {}",
+                token_pair.input_token().symbol_name(),
+                token_pair.output_token().symbol_name()
+            ))));
+        }
+
+        let route_hops: Vec<&[Hop]> = routes.iter().map(|(_, _, hops)| hops.as_slice()).collect();
+        let (allocated, outputs) = allocate(amount_in.raw(), self.steps, &route_hops)?;
+
+        let mut expected_out = U256::zero();
+        let mut legs = vec![];
+        for ((dex_index, path, _), (allocated, output)) in
+            routes.into_iter().zip(allocated.into_iter().zip(outputs))
+        {
+            if allocated.is_zero() {
+                continue;
+            }
+            expected_out += output;
+            legs.push(SplitLeg {
+                dex_index,
+                dex_name: self.dexes[dex_index].name().to_owned(),
+                path,
+                amount_in: TokenAmount::new(allocated, amount_in.decimals()),
+                expected_out: TokenAmount::new(output, output_decimals),
+            });
+        }
+
+        Ok(SplitPlan {
+            amount_in,
+            expected_out: TokenAmount::new(expected_out, output_decimals),
+            legs,
+        })
+    }
+
+    /// Sends every leg of `plan` in turn. A failed leg is reported in its
+    /// `LegFill` and does not stop the others. An `options.slippage` of
+    /// `MinOutput` is shared out between the legs by expected output.
+    pub async fn execute(
+        &self,
+        plan: &SplitPlan<M>,
+        wallet_and_provider: Arc<M>,
+        options: &SwapOptions,
+    ) -> Vec<LegFill>
+    where
+        M: Signing,
+    {
+        let mut fills = Vec::with_capacity(plan.legs.len());
+        for leg in &plan.legs {
+            let mut leg_options = options.clone();
+            if let Slippage::MinOutput(min_output) = options.slippage {
+                let leg_min_output = min_output
+                    .mul_div(leg.expected_out.raw(), plan.expected_out.raw())
+                    .unwrap_or(min_output);
+                leg_options.slippage = Slippage::MinOutput(leg_min_output);
+            }
+
+            let fill = self.dexes[leg.dex_index]
+                .swap_path_with_options(
+                    &leg.path,
+                    leg.amount_in,
+                    wallet_and_provider.clone(),
+                    &leg_options,
+                )
+                .await;
+            if let Err(err) = &fill {
+                log::error!(
+                    "{}: split leg {} along {} failed: {}",
+                    leg.dex_name,
+                    leg.amount_in,
+                    leg.path.symbol_names(),
+                    err
+                );
+            }
+
+            fills.push(LegFill {
+                dex_name: leg.dex_name.clone(),
+                route: leg.path.symbol_names(),
+                amount_in: leg.amount_in,
+                expected_out: leg.expected_out,
+                fill,
+            });
+        }
+        fills
+    }
+
+    /// Plans `amount_in` of `token_pair` for the wallet and executes it.
+    pub async fn swap(
+        &self,
+        token_pair: &TokenPair<M>,
+        amount_in: TokenAmount,
+        wallet_and_provider: Arc<M>,
+        options: &SwapOptions,
+    ) -> Result<Vec<LegFill>, Box<dyn Error + Send + Sync + 'static>>
+    where
+        M: Signing,
+    {
+        let plan = self
+            .plan(token_pair, amount_in, wallet_and_provider.signer_address())
+            .await?;
+        Ok(self.execute(&plan, wallet_and_provider, options).await)
+    }
+
+    /// Direct and intermediate routes on every DEX whose pools could be read,
+    /// reading at most `MAX_CONCURRENT_FETCHES` routes at a time.
+    async fn fetch_routes(
+        &self,
+        token_pair: &TokenPair<M>,
+        trader: Address,
+    ) -> Vec<(usize, SwapPath<M>, Vec<Hop>)> {
+        let endpoints = [
+            token_pair.input_token().address(),
+            token_pair.output_token().address(),
+        ];
+        let mut candidates = vec![];
+        for dex_index in 0..self.dexes.len() {
+            candidates.push((dex_index, SwapPath::from(token_pair)));
+            for intermediate in &self.intermediates {
+                if !endpoints.contains(&intermediate.address()) {
+                    candidates.push((
+                        dex_index,
+                        SwapPath::via(token_pair, vec![intermediate.clone()]),
+                    ));
+                }
+            }
+        }
+
+        let fetched: Vec<_> = stream::iter(
+            candidates
+                .iter()
+                .map(|(dex_index, path)| self.dexes[*dex_index].fetch_hops(path, trader)),
+        )
+        .buffered(MAX_CONCURRENT_FETCHES)
+        .collect()
+        .await;
+
+        candidates
+            .into_iter()
+            .zip(fetched)
+            .filter_map(|((dex_index, path), hops)| match hops {
+                Ok(hops) => Some((dex_index, path, hops)),
+                Err(err) => {
+                    log::debug!(
+                        "{}: no route along {}: {}",
+                        self.dexes[dex_index].name(),
+                        path.symbol_names(),
+                        err
+                    );
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+/// `amount` cut into at most `steps` slices, the remainder going to the
+/// first one.
+fn slices(amount: U256, steps: usize) -> Vec<U256> {
+    if amount.is_zero() {
+        return vec![];
+    }
+    let steps = U256::from(steps).min(amount);
+    let slice = amount / steps;
+    let mut slices = vec![slice; steps.as_usize()];
+    slices[0] += amount % steps;
+    slices
+}
+
+/// Hands out `amount_in` in `steps` slices, each to the route that adds the
+/// most output for it. Returns the input and output of every route.
+fn allocate(
+    amount_in: U256,
+    steps: usize,
+    routes: &[&[Hop]],
+) -> Result<(Vec<U256>, Vec<U256>), AmmError> {
+    let mut allocated = vec![U256::zero(); routes.len()];
+    let mut outputs = vec![U256::zero(); routes.len()];
+    for slice in slices(amount_in, steps) {
+        let mut best: Option<(usize, U256, U256)> = None;
+        for (index, hops) in routes.iter().enumerate() {
+            let Ok(output) = route_output(allocated[index] + slice, hops) else {
+                continue;
+            };
+            let gain = output.saturating_sub(outputs[index]);
+            if best.is_none_or(|(_, _, best_gain)| gain > best_gain) {
+                best = Some((index, output, gain));
+            }
+        }
+
+        let (index, output, _) = best.ok_or(AmmError::InsufficientLiquidity)?;
+        allocated[index] += slice;
+        outputs[index] = output;
+    }
+    Ok((allocated, outputs))
+}
+
+fn route_output(amount_in: U256, hops: &[Hop]) -> Result<U256, AmmError> {
+    Ok(get_amounts_out(amount_in, hops)?[hops.len()])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ether(amount: u64) -> U256 {
+        U256::exp10(18) * amount
+    }
+
+    fn pool(reserve_in: u64, reserve_out: u64) -> Hop {
+        Hop::new(ether(reserve_in), ether(reserve_out), 30)
+    }
+
+    #[test]
+    fn slices_give_the_remainder_to_the_first() {
+        assert_eq!(
+            slices(U256::from(10), 3),
+            vec![U256::from(4), U256::from(3), U256::from(3)]
+        );
+        assert_eq!(slices(U256::from(9), 3), vec![U256::from(3); 3]);
+    }
+
+    #[test]
+    fn slices_never_exceed_the_amount() {
+        assert_eq!(slices(U256::from(2), 5), vec![U256::one(); 2]);
+        assert!(slices(U256::zero(), 5).is_empty());
+        let total = slices(ether(7), 20)
+            .into_iter()
+            .fold(U256::zero(), |total, slice| total + slice);
+        assert_eq!(total, ether(7));
+    }
+
+    #[test]
+    fn small_orders_take_the_best_price() {
+        let deep = [pool(10_000, 20_000)];
+        let shallow = [pool(100, 210)];
+        let (allocated, outputs) = allocate(ether(1) / 1_000, 4, &[&deep, &shallow]).unwrap();
+
+        assert_eq!(allocated, vec![U256::zero(), ether(1) / 1_000]);
+        assert_eq!(outputs[1], route_output(allocated[1], &shallow).unwrap());
+    }
+
+    #[test]
+    fn large_orders_split_across_unequal_pools() {
+        let deep = [pool(3_000, 6_000)];
+        let shallow = [pool(1_000, 2_000)];
+        let amount_in = ether(400);
+        let (allocated, outputs) = allocate(amount_in, 100, &[&deep, &shallow]).unwrap();
+
+        assert_eq!(allocated[0] + allocated[1], amount_in);
+        // Equal prices: the optimum splits in proportion to depth, 3:1
+        assert_eq!(allocated[0], ether(300));
+        assert_eq!(allocated[1], ether(100));
+        for (index, hops) in [&deep[..], &shallow[..]].iter().enumerate() {
+            assert_eq!(
+                outputs[index],
+                route_output(allocated[index], hops).unwrap()
+            );
+        }
+
+        let split = outputs[0] + outputs[1];
+        assert!(split > route_output(amount_in, &deep).unwrap());
+        assert!(split > route_output(amount_in, &shallow).unwrap());
+    }
+
+    #[test]
+    fn allocation_needs_a_usable_route() {
+        let empty = [Hop::new(U256::zero(), ether(1), 30)];
+        assert_eq!(
+            allocate(ether(1), 4, &[&empty]),
+            Err(AmmError::InsufficientLiquidity)
+        );
+    }
+}
@@ -0,0 +1,298 @@
+// spread_scanner.rs
+
+use super::dex::{Dex, TokenPair, MAX_CONCURRENT_FETCHES};
+use crate::token::TokenAmount;
+use ethers::{providers::Middleware, types::U256};
+use futures::stream::{self, StreamExt};
+use std::error::Error;
+
+/// Gas a V2 swap is assumed to burn unless configured otherwise.
+const DEFAULT_GAS_PER_SWAP: u64 = 150_000;
+
+/// Native coins of every supported chain have 18 decimals.
+const NATIVE_DECIMALS: u8 = 18;
+
+/// A pair to scan and the input size to quote it with.
+pub struct SpreadQuery<M: Middleware> {
+    pub token_pair: TokenPair<M>,
+    pub amount_in: TokenAmount,
+    /// Price of the chain's native coin in units of the input token, used to
+    /// charge gas against the profit.
+    pub native_price: f64,
+}
+
+impl<M: Middleware> Clone for SpreadQuery<M> {
+    fn clone(&self) -> Self {
+        Self {
+            token_pair: self.token_pair.clone(),
+            amount_in: self.amount_in,
+            native_price: self.native_price,
+        }
+    }
+}
+
+/// Buying the output token on `buy_dex` and selling it back on `sell_dex`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpreadOpportunity {
+    pub input_symbol: String,
+    pub output_symbol: String,
+    pub buy_dex: String,
+    pub sell_dex: String,
+    pub amount_in: TokenAmount,
+    /// Output token received on `buy_dex` and sold on `sell_dex`.
+    pub bought: TokenAmount,
+    /// Input token received back from `sell_dex`.
+    pub amount_out: TokenAmount,
+    /// Gas for both swaps, in the input token.
+    pub gas_cost: TokenAmount,
+    /// `amount_out - amount_in - gas_cost` in whole input tokens.
+    pub net_profit: f64,
+    /// `net_profit` relative to `amount_in`.
+    pub spread: f64,
+}
+
+/// Quotes pairs on every registered DEX and ranks the round trips between
+/// them. Quotes come from the routers, so swap fees are already taken out
+/// of every amount.
+pub struct SpreadScanner<M: Middleware + 'static> {
+    dexes: Vec<Box<dyn Dex<M>>>,
+    gas_per_swap: u64,
+    min_spread: f64,
+}
+
+impl<M: Middleware + 'static> Clone for SpreadScanner<M> {
+    fn clone(&self) -> Self {
+        Self {
+            dexes: self.dexes.clone(),
+            gas_per_swap: self.gas_per_swap,
+            min_spread: self.min_spread,
+        }
+    }
+}
+
+impl<M: Middleware + 'static> SpreadScanner<M> {
+    /// All `dexes` must be on the same chain.
+    pub fn new(dexes: Vec<Box<dyn Dex<M>>>) -> Self {
+        Self {
+            dexes,
+            gas_per_swap: DEFAULT_GAS_PER_SWAP,
+            min_spread: 0.0,
+        }
+    }
+
+    pub fn with_gas_per_swap(mut self, gas_per_swap: u64) -> Self {
+        self.gas_per_swap = gas_per_swap;
+        self
+    }
+
+    /// Only report round trips whose net spread exceeds `min_spread`
+    /// (0.001 = 0.1%).
+    pub fn with_min_spread(mut self, min_spread: f64) -> Self {
+        self.min_spread = min_spread;
+        self
+    }
+
+    pub fn dexes(&self) -> &[Box<dyn Dex<M>>] {
+        &self.dexes
+    }
+
+    /// Scans all `queries`, at most `MAX_CONCURRENT_FETCHES` quotes at a
+    /// time, and returns the opportunities above the minimum spread, highest
+    /// spread first. Pairs a DEX cannot quote are skipped.
+    pub async fn scan(
+        &self,
+        queries: &[SpreadQuery<M>],
+    ) -> Result<Vec<SpreadOpportunity>, Box<dyn Error + Send + Sync + 'static>> {
+        let provider = self
+            .dexes
+            .first()
+            .ok_or_else(|| Box::new(std::io::Error::other("No DEX to scan")))?
+            .provider();
+        let gas_price = provider.get_gas_price().await?;
+        let round_trip_gas = gas_price
+            .checked_mul(U256::from(self.gas_per_swap) * 2)
+            .ok_or_else(|| Box::new(std::io::Error::other("Gas cost overflows U256")))?;
+        let gas_in_native = TokenAmount::new(round_trip_gas, NATIVE_DECIMALS).to_f64();
+
+        let mut priced = vec![];
+        for query in queries {
+            match TokenAmount::from_f64(
+                gas_in_native * query.native_price,
+                query.amount_in.decimals(),
+            ) {
+                Ok(gas_cost) => priced.push((query, gas_cost)),
+                Err(err) => log::warn!(
+                    "Cannot price gas in {}: {}",
+                    query.token_pair.input_token().symbol_name(),
+                    err
+                ),
+            }
+        }
+
+        // Buy every pair on every DEX
+        let buy_quotes: Vec<(usize, usize)> = (0..priced.len())
+            .flat_map(|query_index| (0..self.dexes.len()).map(move |buy| (query_index, buy)))
+            .collect();
+        let buys: Vec<_> = stream::iter(buy_quotes.iter().map(|(query_index, buy)| {
+            let query = priced[*query_index].0;
+            self.dexes[*buy].get_amount_out(&query.token_pair, query.amount_in)
+        }))
+        .buffered(MAX_CONCURRENT_FETCHES)
+        .collect()
+        .await;
+
+        // Sell what each DEX bought on every other DEX
+        let mut legs = vec![];
+        for ((query_index, buy), bought) in buy_quotes.into_iter().zip(buys) {
+            let token_pair = &priced[query_index].0.token_pair;
+            match bought {
+                Ok(bought) if !bought.is_zero() => {
+                    for sell in (0..self.dexes.len()).filter(|sell| *sell != buy) {
+                        legs.push((query_index, buy, sell, bought));
+                    }
+                }
+                Ok(_) => {}
+                Err(err) => log::debug!(
+                    "{}: cannot quote {}/{}: {}",
+                    self.dexes[buy].name(),
+                    token_pair.input_token().symbol_name(),
+                    token_pair.output_token().symbol_name(),
+                    err
+                ),
+            }
+        }
+        let reverse_pairs: Vec<TokenPair<M>> = priced
+            .iter()
+            .map(|(query, _)| {
+                TokenPair::new(
+                    query.token_pair.output_token().clone(),
+                    query.token_pair.input_token().clone(),
+                )
+            })
+            .collect();
+        let sells: Vec<_> = stream::iter(legs.iter().map(|(query_index, _, sell, bought)| {
+            self.dexes[*sell].get_amount_out(&reverse_pairs[*query_index], *bought)
+        }))
+        .buffered(MAX_CONCURRENT_FETCHES)
+        .collect()
+        .await;
+
+        let mut opportunities = vec![];
+        for ((query_index, buy, sell, bought), amount_out) in legs.into_iter().zip(sells) {
+            let (query, gas_cost) = priced[query_index];
+            let input_symbol = query.token_pair.input_token().symbol_name();
+            let output_symbol = query.token_pair.output_token().symbol_name();
+            let amount_out = match amount_out {
+                Ok(amount_out) => amount_out,
+                Err(err) => {
+                    log::debug!(
+                        "{}: cannot quote {}/{}: {}",
+                        self.dexes[sell].name(),
+                        output_symbol,
+                        input_symbol,
+                        err
+                    );
+                    continue;
+                }
+            };
+
+            let Some((net_profit, spread)) =
+                round_trip_return(query.amount_in, amount_out, gas_cost, self.min_spread)
+            else {
+                continue;
+            };
+            opportunities.push(SpreadOpportunity {
+                input_symbol: input_symbol.to_owned(),
+                output_symbol: output_symbol.to_owned(),
+                buy_dex: self.dexes[buy].name().to_owned(),
+                sell_dex: self.dexes[sell].name().to_owned(),
+                amount_in: query.amount_in,
+                bought,
+                amount_out,
+                gas_cost,
+                net_profit,
+                spread,
+            });
+        }
+        rank(&mut opportunities);
+        Ok(opportunities)
+    }
+}
+
+/// `(net_profit, spread)` of a round trip that turns `amount_in` into
+/// `amount_out` and pays `gas_cost`, all in the input token. `None` unless
+/// the spread beats `min_spread`.
+fn round_trip_return(
+    amount_in: TokenAmount,
+    amount_out: TokenAmount,
+    gas_cost: TokenAmount,
+    min_spread: f64,
+) -> Option<(f64, f64)> {
+    let net_profit = amount_out.to_f64() - amount_in.to_f64() - gas_cost.to_f64();
+    let spread = net_profit / amount_in.to_f64();
+    (spread > min_spread).then_some((net_profit, spread))
+}
+
+/// Highest spread first. Profits are in each pair's own input token, so only
+/// the return is comparable across pairs.
+fn rank(opportunities: &mut [SpreadOpportunity]) {
+    opportunities.sort_by(|a, b| b.spread.total_cmp(&a.spread));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn amount(value: &str, decimals: u8) -> TokenAmount {
+        TokenAmount::parse(value, decimals).unwrap()
+    }
+
+    fn opportunity(input_symbol: &str, net_profit: f64, spread: f64) -> SpreadOpportunity {
+        SpreadOpportunity {
+            input_symbol: input_symbol.to_owned(),
+            output_symbol: "X".to_owned(),
+            buy_dex: "Buy".to_owned(),
+            sell_dex: "Sell".to_owned(),
+            amount_in: TokenAmount::zero(18),
+            bought: TokenAmount::zero(18),
+            amount_out: TokenAmount::zero(18),
+            gas_cost: TokenAmount::zero(18),
+            net_profit,
+            spread,
+        }
+    }
+
+    #[test]
+    fn round_trip_return_charges_gas() {
+        let (net_profit, spread) =
+            round_trip_return(amount("100", 6), amount("101.5", 6), amount("0.5", 6), 0.0).unwrap();
+        assert!((net_profit - 1.0).abs() < 1e-9);
+        assert!((spread - 0.01).abs() < 1e-9);
+    }
+
+    #[test]
+    fn round_trip_return_applies_min_spread() {
+        let (amount_in, amount_out) = (amount("100", 18), amount("101", 18));
+        assert!(round_trip_return(amount_in, amount_out, TokenAmount::zero(18), 0.009).is_some());
+        assert!(round_trip_return(amount_in, amount_out, TokenAmount::zero(18), 0.01).is_none());
+        // Gas can turn a positive spread into a loss
+        assert!(round_trip_return(amount_in, amount_out, amount("2", 18), 0.0).is_none());
+    }
+
+    #[test]
+    fn rank_orders_by_spread_not_profit() {
+        // 50 USDC on a 0.5% spread is less attractive than 0.1 WETH on 2%
+        let mut opportunities = vec![
+            opportunity("USDC", 50.0, 0.005),
+            opportunity("WETH", 0.1, 0.02),
+            opportunity("WBNB", 0.3, 0.01),
+        ];
+        rank(&mut opportunities);
+
+        let symbols: Vec<&str> = opportunities
+            .iter()
+            .map(|opportunity| opportunity.input_symbol.as_str())
+            .collect();
+        assert_eq!(symbols, vec!["WETH", "WBNB", "USDC"]);
+    }
+}
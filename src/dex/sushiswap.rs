@@ -1,63 +1,36 @@
-// pancakeswap.rs
+// sushiswap.rs
 
-use super::dex::BaseDex;
-use super::Dex;
-use async_trait::async_trait;
-use ethers::{prelude::*, types::Address};
-use std::error::Error;
+use super::uniswap_v2::{UniswapV2Config, UniswapV2Dex};
+use crate::token::token::BlockChain;
+use ethers::{providers::Middleware, types::Address};
 use std::sync::Arc;
 
-#[derive(Debug, Clone)]
-pub struct SushiSwap {
-    base_dex: BaseDex,
-}
+static SUSHISWAP_ROUTER_ABI_JSON: &[u8] = include_bytes!("../../resources/SushiSwapRouterABI.json");
 
-static SUSHISWAP_ROUTER_ABI_JSON: &'static [u8] =
-    include_bytes!("../../resources/SushiSwapRouterABI.json");
+/// SushiSwap, deployed with the same router on many chains.
+pub struct SushiSwap;
 
 impl SushiSwap {
-    pub fn new(
-        provider: Arc<NonceManagerMiddleware<SignerMiddleware<Provider<Http>, LocalWallet>>>,
-        router_address: Address,
-    ) -> Self {
-        Self {
-            base_dex: BaseDex::new(provider, router_address),
-        }
-    }
-}
-
-#[async_trait]
-impl Dex for SushiSwap {
-    async fn initialize(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
-        self.base_dex
-            .create_router_contract(SUSHISWAP_ROUTER_ABI_JSON)
-            .await
-    }
-
-    fn clone_box(&self) -> Box<dyn Dex + Send + Sync> {
-        Box::new(self.clone())
-    }
-
-    fn name(&self) -> &str {
-        "SushiSwap"
+    pub fn config(block_chain: BlockChain, router_address: Address) -> UniswapV2Config {
+        UniswapV2Config::new("SushiSwap", block_chain, router_address, 30)
+            .with_router_abi(SUSHISWAP_ROUTER_ABI_JSON)
     }
 
-    fn router_contract(
-        &self,
-    ) -> Result<
-        &Contract<NonceManagerMiddleware<SignerMiddleware<Provider<Http>, LocalWallet>>>,
-        Box<dyn Error + Send + Sync + 'static>,
-    > {
-        self.base_dex.router_contract()
-    }
-
-    fn provider(
-        &self,
-    ) -> Arc<NonceManagerMiddleware<SignerMiddleware<Provider<Http>, LocalWallet>>> {
-        self.base_dex.provider()
+    pub fn dex<M: Middleware>(
+        provider: Arc<M>,
+        block_chain: BlockChain,
+        router_address: Address,
+    ) -> UniswapV2Dex<M> {
+        UniswapV2Dex::new(provider, Self::config(block_chain, router_address))
     }
 
-    fn router_address(&self) -> Address {
-        self.base_dex.router_address()
+    /// SushiSwap on Polygon; use `dex` for any other chain.
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new<M: Middleware>(provider: Arc<M>, router_address: Address) -> UniswapV2Dex<M> {
+        Self::dex(
+            provider,
+            BlockChain::PolygonChain { chain_id: 137 },
+            router_address,
+        )
     }
 }
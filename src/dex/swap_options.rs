@@ -0,0 +1,115 @@
+// swap_options.rs
+
+use crate::fee_policy::FeePolicy;
+use crate::token::{ApprovalManager, TokenAmount};
+use ethers::types::{Address, U256};
+use std::sync::Arc;
+
+const BPS_DENOMINATOR: u32 = 10_000;
+
+/// How much the swap output may fall short of the quote before the router
+/// reverts the transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Slippage {
+    /// Submit `amountOutMin = 0`, accepting any output. Exact-output swaps
+    /// still submit the quoted input as `amountInMax`.
+    Unprotected,
+    /// Quote first and accept at most this many basis points below the quote,
+    /// or above it for exact-output swaps.
+    Bps(u32),
+    /// Submit this exact `amountOutMin`.
+    MinOutput(TokenAmount),
+}
+
+impl Slippage {
+    /// Whether a quote is needed to compute the bound.
+    pub fn needs_quote(&self) -> bool {
+        matches!(self, Slippage::Bps(_))
+    }
+
+    /// Minimum acceptable output for a swap quoted at `quoted_output`.
+    pub fn min_output(&self, quoted_output: TokenAmount) -> TokenAmount {
+        match self {
+            Slippage::Unprotected => TokenAmount::zero(quoted_output.decimals()),
+            Slippage::Bps(bps) => quoted_output
+                .mul_div(
+                    U256::from(BPS_DENOMINATOR.saturating_sub(*bps)),
+                    U256::from(BPS_DENOMINATOR),
+                )
+                .unwrap_or_else(|| TokenAmount::zero(quoted_output.decimals())),
+            Slippage::MinOutput(min_output) => *min_output,
+        }
+    }
+
+    /// Maximum acceptable input for an exact-output swap quoted at `quoted_input`.
+    pub fn max_input(&self, quoted_input: TokenAmount) -> TokenAmount {
+        match self {
+            Slippage::Bps(bps) => quoted_input
+                .mul_div(
+                    U256::from(BPS_DENOMINATOR.saturating_add(*bps)),
+                    U256::from(BPS_DENOMINATOR),
+                )
+                .unwrap_or(quoted_input),
+            Slippage::Unprotected | Slippage::MinOutput(_) => quoted_input,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SwapOptions {
+    pub recipient: Address,
+    pub deadline_secs: u64,
+    pub slippage: Slippage,
+    /// Use the router's `...SupportingFeeOnTransferTokens` functions and
+    /// account the output by the recipient's balance change. Enabled
+    /// automatically when a token on the path is flagged as fee-on-transfer.
+    pub fee_on_transfer: bool,
+    /// When set, the router's allowance on the input token is checked and
+    /// topped up before the swap is sent.
+    pub approval_manager: Option<Arc<ApprovalManager>>,
+    /// Run the exact router call through `eth_call` first and only send it
+    /// when the simulation succeeds, so a failing swap costs no gas.
+    pub simulate: bool,
+    /// Fee policy for the swap transaction. Defaults to the input token's,
+    /// which follows its chain.
+    pub fee_policy: Option<FeePolicy>,
+}
+
+impl SwapOptions {
+    pub fn new(recipient: Address, deadline_secs: u64) -> Self {
+        Self {
+            recipient,
+            deadline_secs,
+            slippage: Slippage::Unprotected,
+            fee_on_transfer: false,
+            approval_manager: None,
+            simulate: false,
+            fee_policy: None,
+        }
+    }
+
+    pub fn with_slippage(mut self, slippage: Slippage) -> Self {
+        self.slippage = slippage;
+        self
+    }
+
+    pub fn with_fee_on_transfer(mut self, fee_on_transfer: bool) -> Self {
+        self.fee_on_transfer = fee_on_transfer;
+        self
+    }
+
+    pub fn with_approval_manager(mut self, approval_manager: Arc<ApprovalManager>) -> Self {
+        self.approval_manager = Some(approval_manager);
+        self
+    }
+
+    pub fn with_simulate(mut self, simulate: bool) -> Self {
+        self.simulate = simulate;
+        self
+    }
+
+    pub fn with_fee_policy(mut self, fee_policy: FeePolicy) -> Self {
+        self.fee_policy = Some(fee_policy);
+        self
+    }
+}
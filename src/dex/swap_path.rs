@@ -0,0 +1,79 @@
+// swap_path.rs
+
+use super::dex::TokenPair;
+use crate::token::Token;
+use ethers::{providers::Middleware, types::Address};
+use std::{error::Error, sync::Arc};
+
+/// Ordered list of tokens a swap routes through, e.g. `[TOKEN, WBNB, USDT]`.
+pub struct SwapPath<M: Middleware> {
+    tokens: Vec<Arc<Box<dyn Token<M>>>>,
+}
+
+impl<M: Middleware> Clone for SwapPath<M> {
+    fn clone(&self) -> Self {
+        Self {
+            tokens: self.tokens.clone(),
+        }
+    }
+}
+
+impl<M: Middleware> SwapPath<M> {
+    pub fn new(
+        tokens: Vec<Arc<Box<dyn Token<M>>>>,
+    ) -> Result<Self, Box<dyn Error + Send + Sync + 'static>> {
+        if tokens.len() < 2 {
+            return Err(Box::new(std::io::Error::other(
+                "Swap path needs at least two tokens",
+            )));
+        }
+        Ok(Self { tokens })
+    }
+
+    /// Routes `token_pair` through the given intermediate tokens.
+    pub fn via(token_pair: &TokenPair<M>, intermediates: Vec<Arc<Box<dyn Token<M>>>>) -> Self {
+        let mut tokens = Vec::with_capacity(intermediates.len() + 2);
+        tokens.push(token_pair.input_token().clone());
+        tokens.extend(intermediates);
+        tokens.push(token_pair.output_token().clone());
+        Self { tokens }
+    }
+
+    pub fn tokens(&self) -> &[Arc<Box<dyn Token<M>>>] {
+        &self.tokens
+    }
+
+    pub fn addresses(&self) -> Vec<Address> {
+        self.tokens.iter().map(|token| token.address()).collect()
+    }
+
+    pub fn input_token(&self) -> &Arc<Box<dyn Token<M>>> {
+        &self.tokens[0]
+    }
+
+    pub fn output_token(&self) -> &Arc<Box<dyn Token<M>>> {
+        &self.tokens[self.tokens.len() - 1]
+    }
+
+    pub fn has_fee_on_transfer_token(&self) -> bool {
+        self.tokens.iter().any(|token| token.is_fee_on_transfer())
+    }
+
+    pub fn hops(&self) -> usize {
+        self.tokens.len() - 1
+    }
+
+    pub fn symbol_names(&self) -> String {
+        self.tokens
+            .iter()
+            .map(|token| token.symbol_name())
+            .collect::<Vec<_>>()
+            .join("->")
+    }
+}
+
+impl<M: Middleware> From<&TokenPair<M>> for SwapPath<M> {
+    fn from(token_pair: &TokenPair<M>) -> Self {
+        Self::via(token_pair, vec![])
+    }
+}
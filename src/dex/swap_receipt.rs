@@ -0,0 +1,346 @@
+// swap_receipt.rs
+
+use crate::token::TokenAmount;
+use ethers::{
+    contract::{parse_log, EthEvent},
+    types::{Address, TransactionReceipt, H256, U256},
+};
+use std::error::Error;
+
+/// Uniswap-V2 pair `Swap` event, emitted once per hop.
+#[derive(Debug, Clone, PartialEq, Eq, EthEvent)]
+#[ethevent(
+    name = "Swap",
+    abi = "Swap(address,uint256,uint256,uint256,uint256,address)"
+)]
+pub struct SwapEvent {
+    #[ethevent(indexed)]
+    pub sender: Address,
+    pub amount0_in: U256,
+    pub amount1_in: U256,
+    pub amount0_out: U256,
+    pub amount1_out: U256,
+    #[ethevent(indexed)]
+    pub to: Address,
+}
+
+/// ERC-20 `Transfer` event.
+#[derive(Debug, Clone, PartialEq, Eq, EthEvent)]
+#[ethevent(name = "Transfer", abi = "Transfer(address,address,uint256)")]
+pub struct TransferEvent {
+    #[ethevent(indexed)]
+    pub from: Address,
+    #[ethevent(indexed)]
+    pub to: Address,
+    pub value: U256,
+}
+
+/// One decoded `Swap` event together with the pair that emitted it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SwapHop {
+    pub pair_address: Address,
+    pub event: SwapEvent,
+}
+
+impl SwapHop {
+    /// `None` when the event's amounts overflow, which no real pair emits.
+    pub fn amount_in(&self) -> Option<U256> {
+        self.event.amount0_in.checked_add(self.event.amount1_in)
+    }
+
+    /// `None` when the event's amounts overflow, which no real pair emits.
+    pub fn amount_out(&self) -> Option<U256> {
+        self.event.amount0_out.checked_add(self.event.amount1_out)
+    }
+}
+
+/// Amounts actually moved by a mined swap transaction.
+#[derive(Debug, Clone)]
+pub struct SwapFill {
+    pub transaction_hash: H256,
+    pub amount_in: TokenAmount,
+    pub amount_out: TokenAmount,
+    pub hops: Vec<SwapHop>,
+}
+
+/// Decodes a swap receipt.
+///
+/// The input is the sum of `input_token` transfers sent by `sender` and the
+/// output is the sum of `output_token` transfers received by `recipient`.
+/// When a token does not emit `Transfer` events the first and last `Swap`
+/// events are used instead.
+pub fn decode_swap_receipt(
+    receipt: &TransactionReceipt,
+    input: (Address, u8),
+    output: (Address, u8),
+    sender: Address,
+    recipient: Address,
+) -> Result<SwapFill, Box<dyn Error + Send + Sync + 'static>> {
+    let (input_address, input_decimals) = input;
+    let (output_address, output_decimals) = output;
+
+    let mut hops = vec![];
+    let mut transferred_in: Option<U256> = None;
+    let mut transferred_out: Option<U256> = None;
+
+    for log in &receipt.logs {
+        if let Ok(event) = parse_log::<SwapEvent>(log.clone()) {
+            hops.push(SwapHop {
+                pair_address: log.address,
+                event,
+            });
+        } else if let Ok(transfer) = parse_log::<TransferEvent>(log.clone()) {
+            if log.address == input_address && transfer.from == sender {
+                transferred_in = Some(add_logged(transferred_in, transfer.value)?);
+            }
+            if log.address == output_address && transfer.to == recipient {
+                transferred_out = Some(add_logged(transferred_out, transfer.value)?);
+            }
+        }
+    }
+
+    let amount_in = match transferred_in {
+        Some(amount_in) => amount_in,
+        None => hops
+            .first()
+            .ok_or_else(|| {
+                Box::new(std::io::Error::other(
+                    "Input amount not found in transaction logs",
+                ))
+            })?
+            .amount_in()
+            .ok_or_else(logged_amount_overflow)?,
+    };
+
+    let amount_out = match transferred_out {
+        Some(amount_out) => amount_out,
+        None => hops
+            .last()
+            .filter(|hop| hop.event.to == recipient)
+            .ok_or_else(|| {
+                Box::new(std::io::Error::other(
+                    "Output amount not found in transaction logs",
+                ))
+            })?
+            .amount_out()
+            .ok_or_else(logged_amount_overflow)?,
+    };
+
+    Ok(SwapFill {
+        transaction_hash: receipt.transaction_hash,
+        amount_in: TokenAmount::new(amount_in, input_decimals),
+        amount_out: TokenAmount::new(amount_out, output_decimals),
+        hops,
+    })
+}
+
+/// Sum of `token` transfers received by `recipient` in the receipt.
+pub fn received_amount(
+    receipt: &TransactionReceipt,
+    token: Address,
+    recipient: Address,
+) -> Result<U256, Box<dyn Error + Send + Sync + 'static>> {
+    receipt
+        .logs
+        .iter()
+        .filter(|log| log.address == token)
+        .filter_map(|log| parse_log::<TransferEvent>(log.clone()).ok())
+        .filter(|transfer| transfer.to == recipient)
+        .try_fold(U256::zero(), |total, transfer| {
+            add_logged(Some(total), transfer.value)
+        })
+}
+
+/// Adds a logged amount to a running total. Token contracts can log any
+/// value, so the sum is checked.
+fn add_logged(
+    total: Option<U256>,
+    value: U256,
+) -> Result<U256, Box<dyn Error + Send + Sync + 'static>> {
+    total
+        .unwrap_or_default()
+        .checked_add(value)
+        .ok_or_else(logged_amount_overflow)
+}
+
+fn logged_amount_overflow() -> Box<dyn Error + Send + Sync + 'static> {
+    Box::new(std::io::Error::other(
+        "Amounts in transaction logs overflow U256",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::abi::{encode, Token};
+    use ethers::types::Log;
+
+    const DECIMALS: u8 = 18;
+
+    fn address(n: u64) -> Address {
+        Address::from_low_u64_be(n)
+    }
+
+    fn topic(address: Address) -> H256 {
+        H256::from(address)
+    }
+
+    fn transfer_log(token: Address, from: Address, to: Address, value: u64) -> Log {
+        Log {
+            address: token,
+            topics: vec![TransferEvent::signature(), topic(from), topic(to)],
+            data: encode(&[Token::Uint(U256::from(value))]).into(),
+            ..Default::default()
+        }
+    }
+
+    /// `Swap` of `amount_in` of token0 for `amount_out` of token1.
+    fn swap_log(
+        pair: Address,
+        sender: Address,
+        to: Address,
+        amount_in: u64,
+        amount_out: u64,
+    ) -> Log {
+        Log {
+            address: pair,
+            topics: vec![SwapEvent::signature(), topic(sender), topic(to)],
+            data: encode(&[
+                Token::Uint(U256::from(amount_in)),
+                Token::Uint(U256::zero()),
+                Token::Uint(U256::zero()),
+                Token::Uint(U256::from(amount_out)),
+            ])
+            .into(),
+            ..Default::default()
+        }
+    }
+
+    fn receipt(logs: Vec<Log>) -> TransactionReceipt {
+        TransactionReceipt {
+            logs,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn single_hop_uses_transfers() {
+        let (router, wallet, pair) = (address(1), address(2), address(3));
+        let (token_in, token_out) = (address(10), address(11));
+        let receipt = receipt(vec![
+            transfer_log(token_in, wallet, pair, 1_000),
+            transfer_log(token_out, pair, wallet, 1_990),
+            swap_log(pair, router, wallet, 1_000, 1_990),
+        ]);
+
+        let fill = decode_swap_receipt(
+            &receipt,
+            (token_in, DECIMALS),
+            (token_out, DECIMALS),
+            wallet,
+            wallet,
+        )
+        .unwrap();
+
+        assert_eq!(fill.amount_in.raw(), U256::from(1_000));
+        assert_eq!(fill.amount_out.raw(), U256::from(1_990));
+        assert_eq!(fill.hops.len(), 1);
+        assert_eq!(fill.hops[0].pair_address, pair);
+    }
+
+    #[test]
+    fn multi_hop_ignores_intermediate_transfers() {
+        let (router, wallet) = (address(1), address(2));
+        let (first_pair, second_pair) = (address(3), address(4));
+        let (token_in, middle, token_out) = (address(10), address(11), address(12));
+        let receipt = receipt(vec![
+            transfer_log(token_in, wallet, first_pair, 1_000),
+            transfer_log(middle, first_pair, second_pair, 500),
+            swap_log(first_pair, router, second_pair, 1_000, 500),
+            transfer_log(token_out, second_pair, wallet, 2_000),
+            swap_log(second_pair, router, wallet, 500, 2_000),
+        ]);
+
+        let fill = decode_swap_receipt(
+            &receipt,
+            (token_in, DECIMALS),
+            (token_out, DECIMALS),
+            wallet,
+            wallet,
+        )
+        .unwrap();
+
+        assert_eq!(fill.amount_in.raw(), U256::from(1_000));
+        assert_eq!(fill.amount_out.raw(), U256::from(2_000));
+        assert_eq!(
+            fill.hops
+                .iter()
+                .map(|hop| hop.pair_address)
+                .collect::<Vec<_>>(),
+            vec![first_pair, second_pair]
+        );
+    }
+
+    #[test]
+    fn falls_back_to_swap_events_without_transfers() {
+        let (router, wallet, pair) = (address(1), address(2), address(3));
+        let (token_in, token_out) = (address(10), address(11));
+        // The output token emits no `Transfer`, e.g. a non-standard token
+        let receipt = receipt(vec![
+            transfer_log(token_in, wallet, pair, 1_000),
+            swap_log(pair, router, wallet, 1_000, 1_990),
+        ]);
+
+        let fill = decode_swap_receipt(
+            &receipt,
+            (token_in, DECIMALS),
+            (token_out, DECIMALS),
+            wallet,
+            wallet,
+        )
+        .unwrap();
+
+        assert_eq!(fill.amount_in.raw(), U256::from(1_000));
+        assert_eq!(fill.amount_out.raw(), U256::from(1_990));
+    }
+
+    #[test]
+    fn missing_amounts_are_an_error() {
+        let receipt = receipt(vec![]);
+        assert!(decode_swap_receipt(
+            &receipt,
+            (address(10), DECIMALS),
+            (address(11), DECIMALS),
+            address(2),
+            address(2),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn overflowing_transfers_are_an_error() {
+        let (wallet, pair) = (address(2), address(3));
+        let (token_in, token_out) = (address(10), address(11));
+        let mut overflowing = transfer_log(token_out, pair, wallet, 0);
+        overflowing.data = encode(&[Token::Uint(U256::MAX)]).into();
+        let receipt = receipt(vec![
+            transfer_log(token_in, wallet, pair, 1_000),
+            overflowing,
+            transfer_log(token_out, pair, wallet, 1),
+        ]);
+
+        assert!(decode_swap_receipt(
+            &receipt,
+            (token_in, DECIMALS),
+            (token_out, DECIMALS),
+            wallet,
+            wallet,
+        )
+        .is_err());
+        assert!(received_amount(&receipt, token_out, wallet).is_err());
+        assert_eq!(
+            received_amount(&receipt, token_in, pair).unwrap(),
+            U256::from(1_000)
+        );
+    }
+}
@@ -0,0 +1,190 @@
+// uniswap_v2.rs
+
+use super::dex::{router_factory, router_wrapped_native, BaseDex};
+use super::Dex;
+use crate::chain::ChainInfo;
+use crate::token::token::BlockChain;
+use async_trait::async_trait;
+use ethers::{prelude::*, types::Address};
+use std::borrow::Cow;
+use std::error::Error;
+use std::sync::{Arc, OnceLock};
+
+static UNISWAP_V2_ROUTER_ABI_JSON: &[u8] =
+    include_bytes!("../../resources/UniswapV2RouterABI.json");
+
+/// Where a fork's swap fee comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeeModel {
+    /// Every pair charges the configured `fee_bps`.
+    Fixed,
+    /// Each pair reports its fee through `swapFee()` in tenths of a percent
+    /// (BiSwap).
+    PairSwapFee,
+    /// The router reports the fee for a trade through
+    /// `transactionFee(tokenIn, tokenOut, sender)` in basis points (BabyDoge).
+    RouterTransactionFee,
+}
+
+/// Runtime description of a Uniswap-V2 fork.
+#[derive(Debug, Clone)]
+pub struct UniswapV2Config {
+    pub name: String,
+    pub block_chain: BlockChain,
+    pub router_address: Address,
+    /// Read once from the router's `factory()` when not set.
+    pub factory_address: Option<Address>,
+    /// Swap fee taken by the pools, in basis points (30 on Uniswap V2).
+    pub fee_bps: u32,
+    /// How per-pair fees are discovered; `fee_bps` is the fallback.
+    pub fee_model: FeeModel,
+    /// Router ABI JSON, for forks that rename or extend the Uniswap-V2 router
+    /// functions. The canonical `UniswapV2Router02` ABI is used otherwise.
+    pub router_abi: Option<Cow<'static, [u8]>>,
+}
+
+impl UniswapV2Config {
+    pub fn new(
+        name: impl Into<String>,
+        block_chain: BlockChain,
+        router_address: Address,
+        fee_bps: u32,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            block_chain,
+            router_address,
+            factory_address: None,
+            fee_bps,
+            fee_model: FeeModel::Fixed,
+            router_abi: None,
+        }
+    }
+
+    pub fn with_factory_address(mut self, factory_address: Address) -> Self {
+        self.factory_address = Some(factory_address);
+        self
+    }
+
+    pub fn with_fee_model(mut self, fee_model: FeeModel) -> Self {
+        self.fee_model = fee_model;
+        self
+    }
+
+    pub fn with_router_abi(mut self, router_abi: impl Into<Cow<'static, [u8]>>) -> Self {
+        self.router_abi = Some(router_abi.into());
+        self
+    }
+}
+
+/// A Uniswap-V2 fork configured at runtime. The named DEX types build
+/// presets of this one.
+#[derive(Debug)]
+pub struct UniswapV2Dex<M: Middleware> {
+    base_dex: BaseDex<M>,
+    config: UniswapV2Config,
+    /// The router's `factory()`, read once when not configured.
+    factory: OnceLock<Address>,
+    /// The router's `WETH()`, read once.
+    wrapped_native: OnceLock<Address>,
+}
+
+impl<M: Middleware> Clone for UniswapV2Dex<M> {
+    fn clone(&self) -> Self {
+        Self {
+            base_dex: self.base_dex.clone(),
+            config: self.config.clone(),
+            factory: self.factory.clone(),
+            wrapped_native: self.wrapped_native.clone(),
+        }
+    }
+}
+
+impl<M: Middleware> UniswapV2Dex<M> {
+    pub fn new(provider: Arc<M>, config: UniswapV2Config) -> Self {
+        Self {
+            base_dex: BaseDex::new(provider, config.router_address),
+            config,
+            factory: OnceLock::new(),
+            wrapped_native: OnceLock::new(),
+        }
+    }
+
+    pub fn config(&self) -> &UniswapV2Config {
+        &self.config
+    }
+
+    pub fn block_chain(&self) -> BlockChain {
+        self.config.block_chain.clone()
+    }
+}
+
+#[async_trait]
+impl<M: Middleware + 'static> Dex<M> for UniswapV2Dex<M> {
+    async fn initialize(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let router_abi = self
+            .config
+            .router_abi
+            .as_deref()
+            .unwrap_or(UNISWAP_V2_ROUTER_ABI_JSON);
+        self.base_dex.create_router_contract(router_abi).await
+    }
+
+    fn clone_box(&self) -> Box<dyn Dex<M> + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    fn router_contract(&self) -> Result<&Contract<M>, Box<dyn Error + Send + Sync + 'static>> {
+        self.base_dex.router_contract()
+    }
+
+    fn provider(&self) -> Arc<M> {
+        self.base_dex.provider()
+    }
+
+    fn router_address(&self) -> Address {
+        self.base_dex.router_address()
+    }
+
+    fn chain_info(&self) -> Option<&'static ChainInfo> {
+        self.config.block_chain.info()
+    }
+
+    fn factory_address(&self) -> Option<Address> {
+        self.config.factory_address
+    }
+
+    async fn factory(&self) -> Result<Address, Box<dyn Error + Send + Sync + 'static>> {
+        if let Some(factory_address) = self
+            .config
+            .factory_address
+            .or_else(|| self.factory.get().copied())
+        {
+            return Ok(factory_address);
+        }
+        let factory_address = router_factory(self.router_contract()?).await?;
+        Ok(*self.factory.get_or_init(|| factory_address))
+    }
+
+    async fn wrapped_native_address(
+        &self,
+    ) -> Result<Address, Box<dyn Error + Send + Sync + 'static>> {
+        if let Some(wrapped_native) = self.wrapped_native.get() {
+            return Ok(*wrapped_native);
+        }
+        let wrapped_native = router_wrapped_native(self).await?;
+        Ok(*self.wrapped_native.get_or_init(|| wrapped_native))
+    }
+
+    fn fee_bps(&self) -> u32 {
+        self.config.fee_bps
+    }
+
+    fn fee_model(&self) -> FeeModel {
+        self.config.fee_model
+    }
+}
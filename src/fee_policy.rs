@@ -0,0 +1,367 @@
+// fee_policy.rs
+
+use crate::signer::Signing;
+use crate::token::token::BlockChain;
+use ethers::{
+    providers::Middleware,
+    types::{
+        transaction::eip2718::TypedTransaction, BlockNumber, Eip1559TransactionRequest, FeeHistory,
+        TransactionRequest, U256,
+    },
+};
+use std::error::Error;
+use std::fmt;
+
+const BPS_DENOMINATOR: u32 = 10_000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FeeError {
+    /// The fee per gas the policy would pay exceeds `max_fee_per_gas`.
+    FeeAboveCap {
+        fee_per_gas: U256,
+        max_fee_per_gas: U256,
+    },
+    /// The padded gas estimate exceeds `max_gas_limit`.
+    GasLimitAboveCap {
+        gas_limit: U256,
+        max_gas_limit: U256,
+    },
+}
+
+impl fmt::Display for FeeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FeeError::FeeAboveCap {
+                fee_per_gas,
+                max_fee_per_gas,
+            } => write!(
+                f,
+                "Fee per gas {} exceeds the cap of {}",
+                fee_per_gas, max_fee_per_gas
+            ),
+            FeeError::GasLimitAboveCap {
+                gas_limit,
+                max_gas_limit,
+            } => write!(
+                f,
+                "Gas limit {} exceeds the cap of {}",
+                gas_limit, max_gas_limit
+            ),
+        }
+    }
+}
+
+impl Error for FeeError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FeeMode {
+    /// A single `gasPrice` from `eth_gasPrice`, for chains whose EIP-1559
+    /// market is not worth following.
+    Legacy,
+    /// `maxPriorityFeePerGas` from the given percentile of the rewards paid in
+    /// the last `history_blocks` blocks (`eth_feeHistory`), never below
+    /// `min_priority_fee`. `maxFeePerGas` allows the base fee to double.
+    Eip1559 {
+        reward_percentile: f64,
+        history_blocks: u64,
+        min_priority_fee: U256,
+    },
+}
+
+/// How write transactions are priced and how much gas they may use.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeePolicy {
+    pub mode: FeeMode,
+    /// The estimated gas limit is padded by this factor in basis points, e.g.
+    /// `12_000` for 1.2x.
+    pub gas_limit_multiplier_bps: u32,
+    /// Abort the send when the padded gas limit is above this.
+    pub max_gas_limit: Option<U256>,
+    /// Abort the send when the gas price, or base fee plus priority fee, is
+    /// above this. `maxFeePerGas` is clamped to it otherwise.
+    pub max_fee_per_gas: Option<U256>,
+}
+
+impl FeePolicy {
+    pub fn legacy() -> Self {
+        Self::new(FeeMode::Legacy)
+    }
+
+    pub fn eip1559() -> Self {
+        Self::new(FeeMode::Eip1559 {
+            reward_percentile: 50.0,
+            history_blocks: 10,
+            min_priority_fee: U256::zero(),
+        })
+    }
+
+    /// Defaults per chain from the registry: EIP-1559 with the chain's
+    /// minimum priority fee where it supports it, legacy pricing otherwise
+    /// (BSC and unknown chains).
+    pub fn for_chain(block_chain: &BlockChain) -> Self {
+        match block_chain.info() {
+            Some(info) if info.eip1559 => Self::new(FeeMode::Eip1559 {
+                reward_percentile: 50.0,
+                history_blocks: 10,
+                min_priority_fee: info.min_priority_fee,
+            }),
+            _ => Self::legacy(),
+        }
+    }
+
+    fn new(mode: FeeMode) -> Self {
+        Self {
+            mode,
+            gas_limit_multiplier_bps: 12_000,
+            max_gas_limit: None,
+            max_fee_per_gas: None,
+        }
+    }
+
+    pub fn with_gas_limit_multiplier_bps(mut self, gas_limit_multiplier_bps: u32) -> Self {
+        self.gas_limit_multiplier_bps = gas_limit_multiplier_bps;
+        self
+    }
+
+    pub fn with_max_gas_limit(mut self, max_gas_limit: U256) -> Self {
+        self.max_gas_limit = Some(max_gas_limit);
+        self
+    }
+
+    pub fn with_max_fee_per_gas(mut self, max_fee_per_gas: U256) -> Self {
+        self.max_fee_per_gas = Some(max_fee_per_gas);
+        self
+    }
+
+    fn check_fee(&self, fee_per_gas: U256) -> Result<(), FeeError> {
+        match self.max_fee_per_gas {
+            Some(max_fee_per_gas) if fee_per_gas > max_fee_per_gas => Err(FeeError::FeeAboveCap {
+                fee_per_gas,
+                max_fee_per_gas,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// `(maxPriorityFeePerGas, maxFeePerGas)` from a one-percentile fee
+    /// history: the median of the non-empty blocks' rewards, never below
+    /// `min_priority_fee`, on top of twice the next block's base fee.
+    fn eip1559_fees(
+        &self,
+        fee_history: &FeeHistory,
+        min_priority_fee: U256,
+    ) -> Result<(U256, U256), Box<dyn Error + Send + Sync>> {
+        // The last entry is the base fee of the next block
+        let base_fee = fee_history
+            .base_fee_per_gas
+            .last()
+            .copied()
+            .ok_or_else(|| Box::new(std::io::Error::other("Fee history has no base fee")))?;
+
+        // Empty blocks report a zero reward, which says nothing about the market
+        let mut rewards: Vec<U256> = fee_history
+            .reward
+            .iter()
+            .filter_map(|block_rewards| block_rewards.first().copied())
+            .filter(|reward| !reward.is_zero())
+            .collect();
+        rewards.sort();
+        let priority_fee = rewards
+            .get(rewards.len() / 2)
+            .copied()
+            .unwrap_or_default()
+            .max(min_priority_fee);
+
+        self.check_fee(base_fee + priority_fee)?;
+        let mut max_fee: U256 = base_fee * 2 + priority_fee;
+        if let Some(max_fee_per_gas) = self.max_fee_per_gas {
+            max_fee = max_fee.min(max_fee_per_gas);
+        }
+        Ok((priority_fee, max_fee))
+    }
+
+    /// Converts `tx` to the policy's transaction type and sets its fee fields.
+    pub async fn apply_fees<M: Middleware + 'static>(
+        &self,
+        client: &M,
+        tx: &mut TypedTransaction,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        match &self.mode {
+            FeeMode::Legacy => {
+                if let TypedTransaction::Eip1559(request) = tx {
+                    let request: TransactionRequest = request.clone().into();
+                    *tx = TypedTransaction::Legacy(request);
+                }
+
+                let gas_price = client.get_gas_price().await?;
+                self.check_fee(gas_price)?;
+                tx.set_gas_price(gas_price);
+            }
+            FeeMode::Eip1559 {
+                reward_percentile,
+                history_blocks,
+                min_priority_fee,
+            } => {
+                let fee_history = client
+                    .fee_history(*history_blocks, BlockNumber::Latest, &[*reward_percentile])
+                    .await?;
+                let (priority_fee, max_fee) = self.eip1559_fees(&fee_history, *min_priority_fee)?;
+
+                let mut request: Eip1559TransactionRequest = tx.clone().into();
+                request.max_priority_fee_per_gas = Some(priority_fee);
+                request.max_fee_per_gas = Some(max_fee);
+                *tx = TypedTransaction::Eip1559(request);
+            }
+        }
+        Ok(())
+    }
+
+    /// Pads an estimated gas limit by the multiplier and checks it against
+    /// the cap.
+    pub fn gas_limit(&self, estimated_gas: U256) -> Result<U256, FeeError> {
+        let gas_limit =
+            estimated_gas * U256::from(self.gas_limit_multiplier_bps) / U256::from(BPS_DENOMINATOR);
+        match self.max_gas_limit {
+            Some(max_gas_limit) if gas_limit > max_gas_limit => Err(FeeError::GasLimitAboveCap {
+                gas_limit,
+                max_gas_limit,
+            }),
+            _ => Ok(gas_limit),
+        }
+    }
+
+    /// Prices `tx` and sets a padded gas limit estimated from the signer's
+    /// address.
+    pub async fn apply<M: Signing + 'static>(
+        &self,
+        client: &M,
+        tx: &mut TypedTransaction,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        tx.set_from(client.signer_address());
+        self.apply_fees(client, tx).await?;
+
+        let estimated_gas = client.estimate_gas(tx, None).await?;
+        tx.set_gas(self.gas_limit(estimated_gas)?);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fee_history(base_fees: &[u64], rewards: &[u64]) -> FeeHistory {
+        FeeHistory {
+            base_fee_per_gas: base_fees.iter().map(|fee| U256::from(*fee)).collect(),
+            gas_used_ratio: vec![0.5; rewards.len()],
+            oldest_block: U256::zero(),
+            reward: rewards
+                .iter()
+                .map(|reward| vec![U256::from(*reward)])
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn gas_limit_is_padded() {
+        let policy = FeePolicy::legacy();
+        assert_eq!(
+            policy.gas_limit(U256::from(100_000)).unwrap(),
+            U256::from(120_000)
+        );
+        let policy = policy.with_gas_limit_multiplier_bps(15_000);
+        assert_eq!(
+            policy.gas_limit(U256::from(100_001)).unwrap(),
+            U256::from(150_001)
+        );
+    }
+
+    #[test]
+    fn gas_limit_above_cap_is_rejected() {
+        let policy = FeePolicy::legacy().with_max_gas_limit(U256::from(120_000));
+        assert!(policy.gas_limit(U256::from(100_000)).is_ok());
+        assert_eq!(
+            policy.gas_limit(U256::from(100_001)),
+            Err(FeeError::GasLimitAboveCap {
+                gas_limit: U256::from(120_001),
+                max_gas_limit: U256::from(120_000),
+            })
+        );
+    }
+
+    #[test]
+    fn check_fee_against_cap() {
+        assert!(FeePolicy::legacy().check_fee(U256::MAX).is_ok());
+        let policy = FeePolicy::legacy().with_max_fee_per_gas(U256::from(100));
+        assert!(policy.check_fee(U256::from(100)).is_ok());
+        assert_eq!(
+            policy.check_fee(U256::from(101)),
+            Err(FeeError::FeeAboveCap {
+                fee_per_gas: U256::from(101),
+                max_fee_per_gas: U256::from(100),
+            })
+        );
+    }
+
+    #[test]
+    fn priority_fee_is_median_of_non_empty_blocks() {
+        let policy = FeePolicy::eip1559();
+        let history = fee_history(&[90, 95, 100], &[0, 5, 0, 1, 3]);
+        assert_eq!(
+            policy.eip1559_fees(&history, U256::zero()).unwrap(),
+            (U256::from(3), U256::from(203))
+        );
+    }
+
+    #[test]
+    fn priority_fee_has_a_floor() {
+        let policy = FeePolicy::eip1559();
+        let history = fee_history(&[100], &[1, 2, 3]);
+        assert_eq!(
+            policy.eip1559_fees(&history, U256::from(30)).unwrap(),
+            (U256::from(30), U256::from(230))
+        );
+        let empty_blocks = fee_history(&[100], &[0, 0]);
+        assert_eq!(
+            policy.eip1559_fees(&empty_blocks, U256::from(30)).unwrap(),
+            (U256::from(30), U256::from(230))
+        );
+    }
+
+    #[test]
+    fn max_fee_is_clamped_to_cap() {
+        let policy = FeePolicy::eip1559().with_max_fee_per_gas(U256::from(150));
+        let history = fee_history(&[100], &[10]);
+        assert_eq!(
+            policy.eip1559_fees(&history, U256::zero()).unwrap(),
+            (U256::from(10), U256::from(150))
+        );
+
+        let policy = FeePolicy::eip1559().with_max_fee_per_gas(U256::from(109));
+        assert!(policy.eip1559_fees(&history, U256::zero()).is_err());
+    }
+
+    #[test]
+    fn chain_defaults_follow_the_registry() {
+        let bsc = FeePolicy::for_chain(&BlockChain::BscChain { chain_id: 56 });
+        assert_eq!(bsc.mode, FeeMode::Legacy);
+
+        let polygon = FeePolicy::for_chain(&BlockChain::PolygonChain { chain_id: 137 });
+        assert_eq!(
+            polygon.mode,
+            FeeMode::Eip1559 {
+                reward_percentile: 50.0,
+                history_blocks: 10,
+                min_priority_fee: U256::from(30_000_000_000u64),
+            }
+        );
+    }
+
+    #[test]
+    fn fee_history_without_base_fee_is_an_error() {
+        let history = fee_history(&[], &[10]);
+        assert!(FeePolicy::eip1559()
+            .eip1559_fees(&history, U256::zero())
+            .is_err());
+    }
+}
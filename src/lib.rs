@@ -1,25 +1,51 @@
+pub mod chain;
 pub mod dex;
+pub mod fee_policy;
+pub mod signer;
 pub mod token;
 
+pub use amm::{AmmError, Hop};
 pub use apeswap::ApeSwap;
 pub use apeswap_polygon::ApeSwapPolygon;
+pub use approval_manager::{ApprovalManager, ApprovalPolicy};
+pub use arbitrage::{ArbitrageCycle, CycleLeg, TokenGraph};
 pub use babydoge::BabyDoge;
 pub use bakeryswap::BakerySwap;
 pub use base_token::BaseToken;
 pub use baseswap::BaseSwap;
 pub use biswap::BiSwap;
 pub use bsc_token::BscToken;
+pub use chain::ChainInfo;
+pub use depth::{DepthCurve, DepthPoint};
 pub use dex::Dex;
 use dex::{
-    apeswap, apeswap_polygon, babydoge, bakeryswap, baseswap, biswap, dyfn, meshswap,
-    pancakeswap_base, pancakeswap_bsc, quickswap, sushiswap,
+    amm, apeswap, apeswap_polygon, arbitrage, babydoge, bakeryswap, baseswap, biswap, depth, dyfn,
+    error, meshswap, pancakeswap_base, pancakeswap_bsc, pool, quickswap, split_router,
+    spread_scanner, sushiswap, swap_options, swap_path, swap_receipt, uniswap_v2,
 };
 pub use dyfn::Dyfn;
+pub use error::DexError;
+pub use fee_policy::{FeeError, FeeMode, FeePolicy};
 pub use meshswap::MeshSwap;
 pub use pancakeswap_base::PancakeSwapBase;
 pub use pancakeswap_bsc::PancakeSwapBsc;
+pub use permit::PermitSignature;
 pub use polygon_token::PolygonToken;
+pub use pool::PoolSnapshot;
 pub use quickswap::QuickSwap;
+pub use signer::Signing;
+pub use split_router::{LegFill, SplitLeg, SplitPlan, SplitRouter};
+pub use spread_scanner::{SpreadOpportunity, SpreadQuery, SpreadScanner};
 pub use sushiswap::SushiSwap;
+pub use swap_options::{Slippage, SwapOptions};
+pub use swap_path::SwapPath;
+pub use swap_receipt::SwapFill;
 pub use token::Token;
-use token::{base_token, bsc_token, polygon_token};
+use token::{
+    approval_manager, base_token, bsc_token, permit, polygon_token, token_amount, token_list,
+    wrapped_native_token,
+};
+pub use token_amount::TokenAmount;
+pub use token_list::{TokenList, TokenListError};
+pub use uniswap_v2::{FeeModel, UniswapV2Config, UniswapV2Dex};
+pub use wrapped_native_token::WrappedNativeToken;
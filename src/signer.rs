@@ -0,0 +1,120 @@
+// signer.rs
+
+use async_trait::async_trait;
+use ethers::{
+    providers::Middleware,
+    signers::Signer,
+    types::{transaction::eip712::Eip712, Address, Signature},
+};
+use ethers_middleware::{
+    gas_oracle::{GasOracle, GasOracleMiddleware},
+    policy::Policy,
+    transformer::Transformer,
+    GasEscalatorMiddleware, NonceManagerMiddleware, PolicyMiddleware, SignerMiddleware,
+    TransformerMiddleware,
+};
+use std::error::Error;
+
+/// Marker for middleware stacks that hold a wallet and can send transactions.
+///
+/// Quoting, pair checks and `balance_of`/`allowance` work with any `Middleware`,
+/// including a plain `Provider<Http>`. Write operations such as `Dex::swap_token`,
+/// `Token::approve` and `Token::transfer` are only available when the stack
+/// implements `Signing`, so a read-only DEX or token cannot send by accident.
+///
+/// Custom middlewares wrapping a signing stack can opt in by forwarding to
+/// their inner middleware.
+#[async_trait]
+pub trait Signing: Middleware {
+    fn signer_address(&self) -> Address;
+
+    /// Signs an EIP-712 payload (e.g. an EIP-2612 permit) with the wallet.
+    async fn sign_eip712<T: Eip712 + Send + Sync>(
+        &self,
+        payload: &T,
+    ) -> Result<Signature, Box<dyn Error + Send + Sync>>;
+}
+
+#[async_trait]
+impl<M: Middleware, S: Signer + 'static> Signing for SignerMiddleware<M, S> {
+    fn signer_address(&self) -> Address {
+        self.address()
+    }
+
+    async fn sign_eip712<T: Eip712 + Send + Sync>(
+        &self,
+        payload: &T,
+    ) -> Result<Signature, Box<dyn Error + Send + Sync>> {
+        Ok(self.signer().sign_typed_data(payload).await?)
+    }
+}
+
+#[async_trait]
+impl<M: Signing> Signing for NonceManagerMiddleware<M> {
+    fn signer_address(&self) -> Address {
+        self.inner().signer_address()
+    }
+
+    async fn sign_eip712<T: Eip712 + Send + Sync>(
+        &self,
+        payload: &T,
+    ) -> Result<Signature, Box<dyn Error + Send + Sync>> {
+        self.inner().sign_eip712(payload).await
+    }
+}
+
+#[async_trait]
+impl<M: Signing, G: GasOracle> Signing for GasOracleMiddleware<M, G> {
+    fn signer_address(&self) -> Address {
+        self.inner().signer_address()
+    }
+
+    async fn sign_eip712<T: Eip712 + Send + Sync>(
+        &self,
+        payload: &T,
+    ) -> Result<Signature, Box<dyn Error + Send + Sync>> {
+        self.inner().sign_eip712(payload).await
+    }
+}
+
+#[async_trait]
+impl<M: Signing> Signing for GasEscalatorMiddleware<M> {
+    fn signer_address(&self) -> Address {
+        self.inner().signer_address()
+    }
+
+    async fn sign_eip712<T: Eip712 + Send + Sync>(
+        &self,
+        payload: &T,
+    ) -> Result<Signature, Box<dyn Error + Send + Sync>> {
+        self.inner().sign_eip712(payload).await
+    }
+}
+
+#[async_trait]
+impl<M: Signing, P: Policy> Signing for PolicyMiddleware<M, P> {
+    fn signer_address(&self) -> Address {
+        self.inner().signer_address()
+    }
+
+    async fn sign_eip712<T: Eip712 + Send + Sync>(
+        &self,
+        payload: &T,
+    ) -> Result<Signature, Box<dyn Error + Send + Sync>> {
+        self.inner().sign_eip712(payload).await
+    }
+}
+
+#[async_trait]
+impl<M: Signing, X: Transformer> Signing for TransformerMiddleware<M, X> {
+    fn signer_address(&self) -> Address {
+        self.inner().signer_address()
+    }
+
+    async fn sign_eip712<T: Eip712 + Send + Sync>(
+        &self,
+        payload: &T,
+    ) -> Result<Signature, Box<dyn Error + Send + Sync>> {
+        self.inner().sign_eip712(payload).await
+    }
+}
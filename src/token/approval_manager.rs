@@ -0,0 +1,151 @@
+// approval_manager.rs
+
+use super::token::Token;
+use crate::signer::Signing;
+use ethers::types::{Address, U256};
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApprovalPolicy {
+    /// Approve exactly the amount about to be spent.
+    Exact,
+    /// Approve `U256::MAX` once so later swaps need no approval.
+    Unlimited,
+}
+
+/// Makes sure a spender (usually a router) may move an owner's tokens before
+/// a swap, approving on demand and caching known allowances per
+/// (token, spender).
+#[derive(Debug)]
+pub struct ApprovalManager {
+    owner: Address,
+    policy: ApprovalPolicy,
+    reset_required: HashSet<Address>,
+    allowances: Mutex<HashMap<(Address, Address), U256>>,
+}
+
+impl ApprovalManager {
+    pub fn new(owner: Address, policy: ApprovalPolicy) -> Self {
+        Self {
+            owner,
+            policy,
+            reset_required: HashSet::new(),
+            allowances: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Marks tokens such as USDT that reject changing a non-zero allowance
+    /// to another non-zero value, so it is reset to zero first.
+    pub fn with_reset_required(mut self, tokens: impl IntoIterator<Item = Address>) -> Self {
+        self.reset_required.extend(tokens);
+        self
+    }
+
+    pub fn owner(&self) -> Address {
+        self.owner
+    }
+
+    pub fn policy(&self) -> ApprovalPolicy {
+        self.policy
+    }
+
+    pub fn cached_allowance(&self, token: Address, spender: Address) -> Option<U256> {
+        self.allowances
+            .lock()
+            .unwrap()
+            .get(&(token, spender))
+            .copied()
+    }
+
+    fn set_cached_allowance(&self, token: Address, spender: Address, allowance: U256) {
+        self.allowances
+            .lock()
+            .unwrap()
+            .insert((token, spender), allowance);
+    }
+
+    /// Lowers the cached allowance after `spender` moved `amount` of `token`.
+    /// Unlimited allowances are not consumed by transfers.
+    pub fn record_spent(&self, token: Address, spender: Address, amount: U256) {
+        let mut allowances = self.allowances.lock().unwrap();
+        if let Some(allowance) = allowances.get_mut(&(token, spender)) {
+            if *allowance != U256::MAX {
+                *allowance = allowance.saturating_sub(amount);
+            }
+        }
+    }
+
+    pub fn invalidate(&self, token: Address, spender: Address) {
+        self.allowances.lock().unwrap().remove(&(token, spender));
+    }
+
+    /// Ensures `spender` may move at least `amount` of `token`, approving and
+    /// waiting for the receipt when the current allowance is too low.
+    pub async fn ensure_allowance<M: Signing + 'static>(
+        &self,
+        token: &dyn Token<M>,
+        spender: Address,
+        amount: U256,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let token_address = token.address();
+
+        if let Some(allowance) = self.cached_allowance(token_address, spender) {
+            if allowance >= amount {
+                return Ok(());
+            }
+        }
+
+        let allowance = token.allowance(self.owner, spender).await?;
+        self.set_cached_allowance(token_address, spender, allowance);
+        if allowance >= amount {
+            return Ok(());
+        }
+
+        let target = match self.policy {
+            ApprovalPolicy::Exact => amount,
+            ApprovalPolicy::Unlimited => U256::MAX,
+        };
+
+        log::debug!(
+            "Approve {} of {} for {:?} (current allowance {})",
+            target,
+            token.symbol_name(),
+            spender,
+            allowance
+        );
+
+        if !allowance.is_zero() && self.reset_required.contains(&token_address) {
+            self.reset_allowance(token, spender).await?;
+        }
+
+        if let Err(err) = token.approve_and_wait(spender, target).await {
+            if allowance.is_zero() || self.reset_required.contains(&token_address) {
+                self.invalidate(token_address, spender);
+                return Err(err);
+            }
+            // Tokens such as USDT revert on non-zero to non-zero changes
+            log::warn!(
+                "Approve of {} failed, retrying after reset: {}",
+                token.symbol_name(),
+                err
+            );
+            self.reset_allowance(token, spender).await?;
+            token.approve_and_wait(spender, target).await?;
+        }
+
+        self.set_cached_allowance(token_address, spender, target);
+        Ok(())
+    }
+
+    async fn reset_allowance<M: Signing + 'static>(
+        &self,
+        token: &dyn Token<M>,
+        spender: Address,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        token.approve_and_wait(spender, U256::zero()).await?;
+        self.set_cached_allowance(token.address(), spender, U256::zero());
+        Ok(())
+    }
+}
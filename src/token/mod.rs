@@ -1,11 +1,22 @@
 // mod.rs
 
+pub mod approval_manager;
 pub mod base_token;
 pub mod bsc_token;
+pub mod permit;
 pub mod polygon_token;
+#[allow(clippy::module_inception)]
 pub mod token;
+pub mod token_amount;
+pub mod token_list;
+pub mod wrapped_native_token;
 
+pub use approval_manager::{ApprovalManager, ApprovalPolicy};
 pub use base_token::BaseToken;
 pub use bsc_token::BscToken;
+pub use permit::PermitSignature;
 pub use polygon_token::PolygonToken;
 pub use token::Token;
+pub use token_amount::TokenAmount;
+pub use token_list::{TokenList, TokenListError};
+pub use wrapped_native_token::WrappedNativeToken;
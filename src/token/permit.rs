@@ -0,0 +1,157 @@
+// permit.rs
+
+use ethers::{
+    abi::{encode, Token as AbiToken},
+    types::{
+        transaction::eip712::{EIP712Domain, Eip712},
+        Address, Signature, U256,
+    },
+    utils::keccak256,
+};
+use std::convert::Infallible;
+
+const PERMIT_TYPE: &str =
+    "Permit(address owner,address spender,uint256 value,uint256 nonce,uint256 deadline)";
+
+/// EIP-2612 `Permit` message. The domain separator is read from the token
+/// rather than rebuilt, so tokens with unusual names or versions sign
+/// correctly.
+#[derive(Debug, Clone)]
+pub struct Permit {
+    pub owner: Address,
+    pub spender: Address,
+    pub value: U256,
+    pub nonce: U256,
+    pub deadline: U256,
+    pub chain_id: u64,
+    pub verifying_contract: Address,
+    pub domain_separator: [u8; 32],
+}
+
+impl Eip712 for Permit {
+    type Error = Infallible;
+
+    fn domain_separator(&self) -> Result<[u8; 32], Self::Error> {
+        Ok(self.domain_separator)
+    }
+
+    fn domain(&self) -> Result<EIP712Domain, Self::Error> {
+        Ok(EIP712Domain {
+            name: None,
+            version: None,
+            chain_id: Some(self.chain_id.into()),
+            verifying_contract: Some(self.verifying_contract),
+            salt: None,
+        })
+    }
+
+    fn type_hash() -> Result<[u8; 32], Self::Error> {
+        Ok(keccak256(PERMIT_TYPE))
+    }
+
+    fn struct_hash(&self) -> Result<[u8; 32], Self::Error> {
+        Ok(keccak256(encode(&[
+            AbiToken::FixedBytes(Self::type_hash()?.to_vec()),
+            AbiToken::Address(self.owner),
+            AbiToken::Address(self.spender),
+            AbiToken::Uint(self.value),
+            AbiToken::Uint(self.nonce),
+            AbiToken::Uint(self.deadline),
+        ])))
+    }
+}
+
+/// A signed permit, split into the `(v, r, s)` form taken by `permit` and the
+/// routers' `...WithPermit` functions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PermitSignature {
+    pub owner: Address,
+    pub spender: Address,
+    pub value: U256,
+    pub deadline: U256,
+    pub v: u8,
+    pub r: [u8; 32],
+    pub s: [u8; 32],
+}
+
+impl PermitSignature {
+    pub fn new(permit: &Permit, signature: Signature) -> Self {
+        let mut r = [0u8; 32];
+        let mut s = [0u8; 32];
+        signature.r.to_big_endian(&mut r);
+        signature.s.to_big_endian(&mut s);
+        Self {
+            owner: permit.owner,
+            spender: permit.spender,
+            value: permit.value,
+            deadline: permit.deadline,
+            v: signature.v as u8,
+            r,
+            s,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::signers::{LocalWallet, Signer};
+    use ethers::types::H256;
+
+    // Hardhat's first development account
+    const PRIVATE_KEY: &str = "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+    fn bytes32(hex: &str) -> [u8; 32] {
+        H256::from_slice(&ethers::utils::hex::decode(hex).unwrap()).0
+    }
+
+    /// Permit for the `Test Token`, version 1, on chain 1, with the domain
+    /// separator and digest computed independently of this crate.
+    fn permit(owner: Address) -> Permit {
+        Permit {
+            owner,
+            spender: Address::repeat_byte(0x22),
+            value: U256::exp10(18),
+            nonce: U256::zero(),
+            deadline: U256::MAX,
+            chain_id: 1,
+            verifying_contract: Address::repeat_byte(0x11),
+            domain_separator: bytes32(
+                "891aec0dd3cfc58255f69df736962a1e3c92af3364f982cc53055b951971b20b",
+            ),
+        }
+    }
+
+    #[test]
+    fn type_hash_matches_eip2612() {
+        assert_eq!(
+            Permit::type_hash().unwrap(),
+            bytes32("6e71edae12b1b97f4d1f60370fef10105fa2faae0126114a169c64845d6126c9")
+        );
+    }
+
+    #[test]
+    fn signs_known_vector() {
+        let wallet: LocalWallet = PRIVATE_KEY.parse().unwrap();
+        let permit = permit(wallet.address());
+
+        let digest = permit.encode_eip712().unwrap();
+        assert_eq!(
+            digest,
+            bytes32("811dfe1f3f19cf2bed987afb95b18eaf8e5a08baa83e47710b2b1ec46a8422fd")
+        );
+
+        let signature = wallet.sign_hash(H256::from(digest)).unwrap();
+        let permit_signature = PermitSignature::new(&permit, signature);
+        assert_eq!(permit_signature.owner, wallet.address());
+        assert_eq!(permit_signature.v, 27);
+        assert_eq!(
+            permit_signature.r,
+            bytes32("665c8a75a843a8b50c6c9844b5922287f50ccd8876ddbc8e28a2579c6ebefcaf")
+        );
+        assert_eq!(
+            permit_signature.s,
+            bytes32("771e4abde75cb3831d0253a60b782cd46b1f615253830998183239398ac7de1e")
+        );
+    }
+}
@@ -1,26 +1,32 @@
 // Polygon_token.rs
 
+use super::permit::PermitSignature;
 use super::token::{AnchorToken, BlockChain, Token};
+use crate::fee_policy::FeePolicy;
+use crate::signer::Signing;
 use ethers::{
-    signers::LocalWallet,
+    providers::Middleware,
     types::{Address, U256},
 };
-use ethers_middleware::{
-    providers::{Http, Provider},
-    NonceManagerMiddleware, SignerMiddleware,
-};
 use std::{error::Error, sync::Arc};
 
-#[derive(Clone)]
-pub struct PolygonToken {
-    anchor_token: AnchorToken,
+pub struct PolygonToken<M: Middleware> {
+    anchor_token: AnchorToken<M>,
+}
+
+impl<M: Middleware> Clone for PolygonToken<M> {
+    fn clone(&self) -> Self {
+        Self {
+            anchor_token: self.anchor_token.clone(),
+        }
+    }
 }
 
 #[async_trait::async_trait]
-impl Token for PolygonToken {
+impl<M: Middleware + 'static> Token<M> for PolygonToken<M> {
     fn new(
         block_chain: BlockChain,
-        provider: Arc<NonceManagerMiddleware<SignerMiddleware<Provider<Http>, LocalWallet>>>,
+        provider: Arc<M>,
         address: Address,
         symbol_name: String,
         decimals: Option<u8>,
@@ -30,7 +36,7 @@ impl Token for PolygonToken {
         }
     }
 
-    fn clone_box(&self) -> Box<dyn Token> {
+    fn clone_box(&self) -> Box<dyn Token<M>> {
         Box::new(self.clone())
     }
 
@@ -57,6 +63,22 @@ impl Token for PolygonToken {
         self.anchor_token.decimals()
     }
 
+    fn is_fee_on_transfer(&self) -> bool {
+        self.anchor_token.is_fee_on_transfer()
+    }
+
+    fn set_fee_on_transfer(&mut self, fee_on_transfer: bool) {
+        self.anchor_token.set_fee_on_transfer(fee_on_transfer)
+    }
+
+    fn fee_policy(&self) -> &FeePolicy {
+        self.anchor_token.fee_policy()
+    }
+
+    fn set_fee_policy(&mut self, fee_policy: FeePolicy) {
+        self.anchor_token.set_fee_policy(fee_policy)
+    }
+
     async fn initialize(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
         self.anchor_token.initialize().await
     }
@@ -65,10 +87,46 @@ impl Token for PolygonToken {
         &self,
         spender: Address,
         amount: U256,
-    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+    ) -> Result<(), Box<dyn Error + Send + Sync>>
+    where
+        M: Signing,
+    {
         self.anchor_token.approve(spender, amount).await
     }
 
+    async fn approve_and_wait(
+        &self,
+        spender: Address,
+        amount: U256,
+    ) -> Result<(), Box<dyn Error + Send + Sync>>
+    where
+        M: Signing,
+    {
+        self.anchor_token.approve_and_wait(spender, amount).await
+    }
+
+    async fn supports_permit(&self, owner: Address) -> bool {
+        self.anchor_token.supports_permit(owner).await
+    }
+
+    async fn sign_permit(
+        &self,
+        spender: Address,
+        value: U256,
+        deadline: U256,
+    ) -> Result<PermitSignature, Box<dyn Error + Send + Sync>>
+    where
+        M: Signing,
+    {
+        self.anchor_token
+            .sign_permit(spender, value, deadline)
+            .await
+    }
+
+    async fn fetch_symbol(&self) -> Result<String, Box<dyn Error + Send + Sync>> {
+        self.anchor_token.fetch_symbol().await
+    }
+
     async fn allowance(
         &self,
         owner: Address,
@@ -85,7 +143,10 @@ impl Token for PolygonToken {
         &self,
         recipient: Address,
         amount: U256,
-    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+    ) -> Result<(), Box<dyn Error + Send + Sync>>
+    where
+        M: Signing,
+    {
         self.anchor_token.transfer(recipient, amount).await
     }
 }
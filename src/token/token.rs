@@ -1,50 +1,133 @@
 // token.rs
 
-use ethers::types::U256;
-use ethers::{
-    abi::Abi, contract::Contract, middleware::SignerMiddleware, providers::Http,
-    providers::Provider, signers::LocalWallet, types::Address,
-};
-use ethers_middleware::NonceManagerMiddleware;
+use super::permit::{Permit, PermitSignature};
+use super::token_amount::TokenAmount;
+use crate::chain::ChainInfo;
+use crate::fee_policy::FeePolicy;
+use crate::signer::Signing;
+use ethers::providers::{JsonRpcClient, PendingTransaction};
+use ethers::types::{TransactionReceipt, U256};
+use ethers::{abi::Abi, contract::Contract, providers::Middleware, types::Address};
 
 use std::error::Error;
 use std::sync::Arc;
-static ERC20_TOKEN_ABI_JSON: &'static [u8] = include_bytes!("../../resources/ERC20TokenABI.json");
+use std::time::Duration;
+static ERC20_TOKEN_ABI_JSON: &[u8] = include_bytes!("../../resources/ERC20TokenABI.json");
+static ERC20_PERMIT_ABI_JSON: &[u8] = include_bytes!("../../resources/ERC20PermitABI.json");
 
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub enum BlockChain {
     BscChain { chain_id: u64 },
     PolygonChain { chain_id: u64 },
     BaseChain { chain_id: u64 },
+    EthereumChain { chain_id: u64 },
+    ArbitrumChain { chain_id: u64 },
+    OptimismChain { chain_id: u64 },
+    AvalancheChain { chain_id: u64 },
 }
 
-#[derive(Clone)]
-pub struct AnchorToken {
+impl BlockChain {
+    /// Maps a mainnet chain id to its variant.
+    pub fn from_chain_id(chain_id: u64) -> Option<Self> {
+        match chain_id {
+            56 => Some(BlockChain::BscChain { chain_id }),
+            137 => Some(BlockChain::PolygonChain { chain_id }),
+            8453 => Some(BlockChain::BaseChain { chain_id }),
+            1 => Some(BlockChain::EthereumChain { chain_id }),
+            42161 => Some(BlockChain::ArbitrumChain { chain_id }),
+            10 => Some(BlockChain::OptimismChain { chain_id }),
+            43114 => Some(BlockChain::AvalancheChain { chain_id }),
+            _ => None,
+        }
+    }
+
+    pub fn chain_id(&self) -> u64 {
+        match self {
+            BlockChain::BscChain { chain_id }
+            | BlockChain::PolygonChain { chain_id }
+            | BlockChain::BaseChain { chain_id }
+            | BlockChain::EthereumChain { chain_id }
+            | BlockChain::ArbitrumChain { chain_id }
+            | BlockChain::OptimismChain { chain_id }
+            | BlockChain::AvalancheChain { chain_id } => *chain_id,
+        }
+    }
+
+    /// Registry entry for the chain id, `None` for testnets and unknown ids.
+    pub fn info(&self) -> Option<&'static ChainInfo> {
+        ChainInfo::by_chain_id(self.chain_id())
+    }
+
+    /// Confirmations to wait for, one when the chain is not in the registry.
+    pub fn confirmations(&self) -> usize {
+        self.info().map_or(1, |info| info.confirmations)
+    }
+
+    /// Average block time, `None` when the chain is not in the registry.
+    pub fn block_time(&self) -> Option<Duration> {
+        self.info().map(|info| info.block_time)
+    }
+
+    /// Waits for `pending` to reach the chain's confirmations, polling once
+    /// per block where the block time is known.
+    pub(crate) fn wait_for<'a, P: JsonRpcClient>(
+        &self,
+        pending: PendingTransaction<'a, P>,
+    ) -> PendingTransaction<'a, P> {
+        let pending = pending.confirmations(self.confirmations());
+        match self.block_time() {
+            Some(block_time) => pending.interval(block_time),
+            None => pending,
+        }
+    }
+}
+
+pub struct AnchorToken<M: Middleware> {
     block_chain: BlockChain,
-    provider: Arc<NonceManagerMiddleware<SignerMiddleware<Provider<Http>, LocalWallet>>>,
+    provider: Arc<M>,
     address: Address,
     symbol_name: String,
     decimals: Option<u8>,
+    fee_on_transfer: bool,
+    fee_policy: FeePolicy,
     abi: Abi,
-    token_contract:
-        Option<Contract<NonceManagerMiddleware<SignerMiddleware<Provider<Http>, LocalWallet>>>>,
+    token_contract: Option<Contract<M>>,
+}
+
+impl<M: Middleware> Clone for AnchorToken<M> {
+    fn clone(&self) -> Self {
+        Self {
+            block_chain: self.block_chain.clone(),
+            provider: self.provider.clone(),
+            address: self.address,
+            symbol_name: self.symbol_name.clone(),
+            decimals: self.decimals,
+            fee_on_transfer: self.fee_on_transfer,
+            fee_policy: self.fee_policy.clone(),
+            abi: self.abi.clone(),
+            token_contract: self.token_contract.clone(),
+        }
+    }
 }
 
-impl AnchorToken {
+impl<M: Middleware + 'static> AnchorToken<M> {
     pub fn new(
         block_chain: BlockChain,
-        provider: Arc<NonceManagerMiddleware<SignerMiddleware<Provider<Http>, LocalWallet>>>,
+        provider: Arc<M>,
         address: Address,
         symbol_name: String,
         decimals: Option<u8>,
     ) -> Self {
         let abi = Abi::load(ERC20_TOKEN_ABI_JSON).unwrap();
+        let fee_policy = FeePolicy::for_chain(&block_chain);
         Self {
             block_chain,
             provider,
             address,
             symbol_name,
             decimals,
+            fee_on_transfer: false,
+            fee_policy,
             abi,
             token_contract: None,
         }
@@ -61,12 +144,12 @@ impl AnchorToken {
         Ok(())
     }
 
+    pub fn block_chain(&self) -> BlockChain {
+        self.block_chain.clone()
+    }
+
     pub fn block_chain_id(&self) -> u64 {
-        match &self.block_chain {
-            BlockChain::BscChain { chain_id } => *chain_id,
-            BlockChain::PolygonChain { chain_id } => *chain_id,
-            BlockChain::BaseChain { chain_id } => *chain_id,
-        }
+        self.block_chain.chain_id()
     }
 
     pub fn address(&self) -> Address {
@@ -81,6 +164,22 @@ impl AnchorToken {
         self.decimals
     }
 
+    pub fn is_fee_on_transfer(&self) -> bool {
+        self.fee_on_transfer
+    }
+
+    pub fn set_fee_on_transfer(&mut self, fee_on_transfer: bool) {
+        self.fee_on_transfer = fee_on_transfer;
+    }
+
+    pub fn fee_policy(&self) -> &FeePolicy {
+        &self.fee_policy
+    }
+
+    pub fn set_fee_policy(&mut self, fee_policy: FeePolicy) {
+        self.fee_policy = fee_policy;
+    }
+
     pub async fn initialize(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
         self.create_token_contract().await?;
 
@@ -90,14 +189,11 @@ impl AnchorToken {
             .call()
             .await
             .map_err(|e| {
-                Box::new(std::io::Error::new(
-                    std::io::ErrorKind::Other,
-                    format!(
-                        "Failed to call 'decimals' method for {}: {}",
-                        self.symbol_name(),
-                        e
-                    ),
-                )) as Box<dyn Error + Send + Sync>
+                Box::new(std::io::Error::other(format!(
+                    "Failed to call 'decimals' method for {}: {}",
+                    self.symbol_name(),
+                    e
+                ))) as Box<dyn Error + Send + Sync>
             })?;
 
         self.decimals = Some(decimals);
@@ -105,16 +201,10 @@ impl AnchorToken {
         Ok(())
     }
 
-    pub fn token_contract(
-        &self,
-    ) -> Result<
-        &Contract<NonceManagerMiddleware<SignerMiddleware<Provider<Http>, LocalWallet>>>,
-        Box<dyn Error + Send + Sync>,
-    > {
+    pub fn token_contract(&self) -> Result<&Contract<M>, Box<dyn Error + Send + Sync>> {
         match &self.token_contract {
             Some(contract) => Ok(contract),
-            None => Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::Other,
+            None => Err(Box::new(std::io::Error::other(
                 "Token contract not created",
             ))),
         }
@@ -124,13 +214,99 @@ impl AnchorToken {
         &self,
         spender: Address,
         amount: U256,
-    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+    ) -> Result<(), Box<dyn Error + Send + Sync>>
+    where
+        M: Signing,
+    {
         let contract = self.token_contract()?;
-        let call = contract.method::<_, ()>("approve", (spender, amount))?;
+        let mut call = contract.method::<_, ()>("approve", (spender, amount))?;
+        self.fee_policy
+            .apply(self.provider.as_ref(), &mut call.tx)
+            .await?;
         call.send().await?;
         Ok(())
     }
 
+    pub async fn approve_and_wait(
+        &self,
+        spender: Address,
+        amount: U256,
+    ) -> Result<(), Box<dyn Error + Send + Sync>>
+    where
+        M: Signing,
+    {
+        let contract = self.token_contract()?;
+        let mut call = contract.method::<_, bool>("approve", (spender, amount))?;
+        self.fee_policy
+            .apply(self.provider.as_ref(), &mut call.tx)
+            .await?;
+        let pending = call.send().await?;
+        wait_for_success(pending, "approve", &self.block_chain).await?;
+        Ok(())
+    }
+
+    fn permit_contract(&self) -> Result<Contract<M>, Box<dyn Error + Send + Sync>> {
+        let abi = Abi::load(ERC20_PERMIT_ABI_JSON)?;
+        Ok(Contract::new(self.address, abi, self.provider.clone()))
+    }
+
+    /// Whether the token implements EIP-2612, probed through its
+    /// `DOMAIN_SEPARATOR` and `nonces` views.
+    pub async fn supports_permit(&self, owner: Address) -> bool {
+        let contract = match self.permit_contract() {
+            Ok(contract) => contract,
+            Err(_) => return false,
+        };
+
+        let domain_separator = match contract.method::<_, [u8; 32]>("DOMAIN_SEPARATOR", ()) {
+            Ok(call) => call.call().await,
+            Err(_) => return false,
+        };
+        let nonce = match contract.method::<_, U256>("nonces", owner) {
+            Ok(call) => call.call().await,
+            Err(_) => return false,
+        };
+
+        domain_separator.is_ok() && nonce.is_ok()
+    }
+
+    /// Signs an EIP-2612 permit letting `spender` move `value` until `deadline`.
+    pub async fn sign_permit(
+        &self,
+        spender: Address,
+        value: U256,
+        deadline: U256,
+    ) -> Result<PermitSignature, Box<dyn Error + Send + Sync>>
+    where
+        M: Signing,
+    {
+        let contract = self.permit_contract()?;
+        let owner = self.provider.signer_address();
+
+        let nonce: U256 = contract.method("nonces", owner)?.call().await?;
+        let domain_separator: [u8; 32] = contract.method("DOMAIN_SEPARATOR", ())?.call().await?;
+
+        let permit = Permit {
+            owner,
+            spender,
+            value,
+            nonce,
+            deadline,
+            chain_id: self.block_chain_id(),
+            verifying_contract: self.address,
+            domain_separator,
+        };
+
+        let signature = self.provider.sign_eip712(&permit).await?;
+        Ok(PermitSignature::new(&permit, signature))
+    }
+
+    pub async fn fetch_symbol(&self) -> Result<String, Box<dyn Error + Send + Sync>> {
+        let contract = self.token_contract()?;
+        let symbol: String = contract.method("symbol", ())?.call().await?;
+        Ok(symbol)
+    }
+
     pub async fn allowance(
         &self,
         owner: Address,
@@ -154,37 +330,92 @@ impl AnchorToken {
         &self,
         recipient: Address,
         amount: U256,
-    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+    ) -> Result<(), Box<dyn Error + Send + Sync>>
+    where
+        M: Signing,
+    {
         let contract = self.token_contract()?;
-        let call = contract.method::<_, ()>("transfer", (recipient, amount))?;
+        let mut call = contract.method::<_, ()>("transfer", (recipient, amount))?;
+        self.fee_policy
+            .apply(self.provider.as_ref(), &mut call.tx)
+            .await?;
         call.send().await?;
         Ok(())
     }
 }
 
+pub(crate) async fn wait_for_success<P: JsonRpcClient>(
+    pending: PendingTransaction<'_, P>,
+    method: &str,
+    block_chain: &BlockChain,
+) -> Result<TransactionReceipt, Box<dyn Error + Send + Sync>> {
+    let receipt = block_chain.wait_for(pending).await?.ok_or_else(|| {
+        Box::new(std::io::Error::other(format!(
+            "Transaction receipt of {} is none",
+            method
+        )))
+    })?;
+    if receipt.status != Some(1.into()) {
+        return Err(Box::new(std::io::Error::other(format!(
+            "{} transaction failed",
+            method
+        ))));
+    }
+    Ok(receipt)
+}
+
 #[async_trait::async_trait]
-pub trait Token: Send + Sync {
+pub trait Token<M: Middleware>: Send + Sync {
     fn new(
         block_chain: BlockChain,
-        provider: Arc<NonceManagerMiddleware<SignerMiddleware<Provider<Http>, LocalWallet>>>,
+        provider: Arc<M>,
         address: Address,
         symbol_name: String,
         decimals: Option<u8>,
     ) -> Self
     where
         Self: Sized;
-    fn clone_box(&self) -> Box<dyn Token>;
+    fn clone_box(&self) -> Box<dyn Token<M>>;
     async fn initialize(&mut self) -> Result<(), Box<dyn Error + Send + Sync>>;
     fn block_chain(&self) -> BlockChain;
     fn block_chain_id(&self) -> u64;
     fn address(&self) -> Address;
     fn symbol_name(&self) -> &str;
     fn decimals(&self) -> Option<u8>;
+    /// Whether the token takes a fee on transfer, which requires the
+    /// router's `...SupportingFeeOnTransferTokens` swap functions.
+    fn is_fee_on_transfer(&self) -> bool;
+    fn set_fee_on_transfer(&mut self, fee_on_transfer: bool);
+    /// Fee policy for this token's write transactions, by default the
+    /// chain's.
+    fn fee_policy(&self) -> &FeePolicy;
+    fn set_fee_policy(&mut self, fee_policy: FeePolicy);
     async fn approve(
         &self,
         spender: Address,
         amount: U256,
-    ) -> Result<(), Box<dyn Error + Send + Sync>>;
+    ) -> Result<(), Box<dyn Error + Send + Sync>>
+    where
+        M: Signing;
+    /// Approves `amount` and waits for a successful receipt.
+    async fn approve_and_wait(
+        &self,
+        spender: Address,
+        amount: U256,
+    ) -> Result<(), Box<dyn Error + Send + Sync>>
+    where
+        M: Signing;
+    async fn supports_permit(&self, owner: Address) -> bool;
+    async fn sign_permit(
+        &self,
+        spender: Address,
+        value: U256,
+        deadline: U256,
+    ) -> Result<PermitSignature, Box<dyn Error + Send + Sync>>
+    where
+        M: Signing;
+    /// Reads the token's `symbol()` on-chain.
+    async fn fetch_symbol(&self) -> Result<String, Box<dyn Error + Send + Sync>>;
     async fn allowance(
         &self,
         owner: Address,
@@ -195,11 +426,52 @@ pub trait Token: Send + Sync {
         &self,
         recipient: Address,
         amount: U256,
-    ) -> Result<(), Box<dyn Error + Send + Sync>>;
+    ) -> Result<(), Box<dyn Error + Send + Sync>>
+    where
+        M: Signing;
+
+    fn required_decimals(&self) -> Result<u8, Box<dyn Error + Send + Sync>> {
+        self.decimals().ok_or_else(|| {
+            Box::new(std::io::Error::other(format!(
+                "Decimals of {} are unknown, initialize the token first",
+                self.symbol_name()
+            ))) as Box<dyn Error + Send + Sync>
+        })
+    }
+
+    fn parse_amount(&self, value: &str) -> Result<TokenAmount, Box<dyn Error + Send + Sync>> {
+        Ok(TokenAmount::parse(value, self.required_decimals()?)?)
+    }
+
+    async fn balance(&self, owner: Address) -> Result<TokenAmount, Box<dyn Error + Send + Sync>> {
+        let decimals = self.required_decimals()?;
+        let balance = self.balance_of(owner).await?;
+        Ok(TokenAmount::new(balance, decimals))
+    }
+
+    async fn transfer_amount(
+        &self,
+        recipient: Address,
+        amount: TokenAmount,
+    ) -> Result<(), Box<dyn Error + Send + Sync>>
+    where
+        M: Signing,
+    {
+        let decimals = self.required_decimals()?;
+        if amount.decimals() != decimals {
+            return Err(Box::new(std::io::Error::other(format!(
+                "Amount has {} decimals but {} uses {}",
+                amount.decimals(),
+                self.symbol_name(),
+                decimals
+            ))));
+        }
+        self.transfer(recipient, amount.raw()).await
+    }
 }
 
-impl Clone for Box<dyn Token> {
-    fn clone(&self) -> Box<dyn Token> {
+impl<M: Middleware> Clone for Box<dyn Token<M>> {
+    fn clone(&self) -> Box<dyn Token<M>> {
         self.clone_box()
     }
 }
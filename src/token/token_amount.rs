@@ -0,0 +1,338 @@
+// token_amount.rs
+
+use ethers::types::U256;
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseTokenAmountError {
+    Empty,
+    InvalidDigit(String),
+    TooManyDecimals { given: usize, max: u8 },
+    Overflow,
+}
+
+impl fmt::Display for ParseTokenAmountError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseTokenAmountError::Empty => write!(f, "empty token amount"),
+            ParseTokenAmountError::InvalidDigit(s) => write!(f, "invalid token amount: {}", s),
+            ParseTokenAmountError::TooManyDecimals { given, max } => write!(
+                f,
+                "token amount has {} fractional digits, token supports {}",
+                given, max
+            ),
+            ParseTokenAmountError::Overflow => write!(f, "token amount overflows U256"),
+        }
+    }
+}
+
+impl Error for ParseTokenAmountError {}
+
+/// An exact token quantity: the raw on-chain integer together with the
+/// token's decimals. All arithmetic is performed on the raw integer so no
+/// money path goes through floating point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TokenAmount {
+    raw: U256,
+    decimals: u8,
+}
+
+impl TokenAmount {
+    pub fn new(raw: U256, decimals: u8) -> Self {
+        Self { raw, decimals }
+    }
+
+    pub fn zero(decimals: u8) -> Self {
+        Self::new(U256::zero(), decimals)
+    }
+
+    /// Parses a human readable amount such as `"1.5"` or `"1000"` exactly.
+    pub fn parse(value: &str, decimals: u8) -> Result<Self, ParseTokenAmountError> {
+        let value = value.trim();
+        if value.is_empty() {
+            return Err(ParseTokenAmountError::Empty);
+        }
+
+        let (integer, fraction) = match value.split_once('.') {
+            Some((integer, fraction)) => (integer, fraction),
+            None => (value, ""),
+        };
+
+        if integer.is_empty() && fraction.is_empty() {
+            return Err(ParseTokenAmountError::InvalidDigit(value.to_owned()));
+        }
+        if !integer
+            .chars()
+            .chain(fraction.chars())
+            .all(|c| c.is_ascii_digit())
+        {
+            return Err(ParseTokenAmountError::InvalidDigit(value.to_owned()));
+        }
+
+        // Trailing zeros beyond the token precision carry no value
+        let fraction = fraction.trim_end_matches('0');
+        if fraction.len() > decimals as usize {
+            return Err(ParseTokenAmountError::TooManyDecimals {
+                given: fraction.len(),
+                max: decimals,
+            });
+        }
+
+        let digits = format!(
+            "{}{}{}",
+            integer,
+            fraction,
+            "0".repeat(decimals as usize - fraction.len())
+        );
+        let digits = digits.trim_start_matches('0');
+        if digits.is_empty() {
+            return Ok(Self::zero(decimals));
+        }
+
+        let raw = U256::from_dec_str(digits).map_err(|_| ParseTokenAmountError::Overflow)?;
+        Ok(Self::new(raw, decimals))
+    }
+
+    /// Lossy conversion from a floating point amount, for callers of the f64 helpers.
+    pub fn from_f64(value: f64, decimals: u8) -> Result<Self, ParseTokenAmountError> {
+        if !value.is_finite() || value < 0.0 {
+            return Err(ParseTokenAmountError::InvalidDigit(value.to_string()));
+        }
+        let raw = U256::from_dec_str(&format!("{:.0}", value * 10f64.powi(decimals as i32)))
+            .map_err(|_| ParseTokenAmountError::Overflow)?;
+        Ok(Self::new(raw, decimals))
+    }
+
+    pub fn raw(&self) -> U256 {
+        self.raw
+    }
+
+    pub fn decimals(&self) -> u8 {
+        self.decimals
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.raw.is_zero()
+    }
+
+    /// Lossy conversion to floating point, for display and ratio estimates only.
+    pub fn to_f64(&self) -> f64 {
+        self.to_string().parse().unwrap_or(f64::INFINITY)
+    }
+
+    pub fn checked_add(&self, other: &TokenAmount) -> Option<TokenAmount> {
+        if self.decimals != other.decimals {
+            return None;
+        }
+        self.raw
+            .checked_add(other.raw)
+            .map(|raw| Self::new(raw, self.decimals))
+    }
+
+    pub fn checked_sub(&self, other: &TokenAmount) -> Option<TokenAmount> {
+        if self.decimals != other.decimals {
+            return None;
+        }
+        self.raw
+            .checked_sub(other.raw)
+            .map(|raw| Self::new(raw, self.decimals))
+    }
+
+    pub fn saturating_sub(&self, other: &TokenAmount) -> TokenAmount {
+        self.checked_sub(other)
+            .unwrap_or_else(|| Self::zero(self.decimals))
+    }
+
+    /// Computes `self * numerator / denominator`, rounding down.
+    pub fn mul_div(&self, numerator: U256, denominator: U256) -> Option<TokenAmount> {
+        if denominator.is_zero() {
+            return None;
+        }
+        let product = self.raw.full_mul(numerator) / denominator;
+        U256::try_from(product)
+            .ok()
+            .map(|raw| Self::new(raw, self.decimals))
+    }
+
+    /// Re-expresses the amount with a different number of decimals, rounding
+    /// down when precision is lost. `None` when the result does not fit.
+    pub fn rescale(&self, decimals: u8) -> Option<TokenAmount> {
+        match decimals.cmp(&self.decimals) {
+            Ordering::Equal => Some(*self),
+            Ordering::Greater => {
+                let factor = pow10(decimals - self.decimals)?;
+                self.raw
+                    .checked_mul(factor)
+                    .map(|raw| Self::new(raw, decimals))
+            }
+            Ordering::Less => {
+                // A factor beyond U256 divides every amount down to zero
+                let raw = pow10(self.decimals - decimals)
+                    .map_or(U256::zero(), |factor| self.raw / factor);
+                Some(Self::new(raw, decimals))
+            }
+        }
+    }
+}
+
+/// `10^exponent`, or `None` past 10^77.
+fn pow10(exponent: u8) -> Option<U256> {
+    U256::from(10).checked_pow(U256::from(exponent))
+}
+
+impl fmt::Display for TokenAmount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = self.raw.to_string();
+        let decimals = self.decimals as usize;
+        if decimals == 0 {
+            return write!(f, "{}", digits);
+        }
+
+        let padded = format!("{:0>width$}", digits, width = decimals + 1);
+        let (integer, fraction) = padded.split_at(padded.len() - decimals);
+        let fraction = fraction.trim_end_matches('0');
+        if fraction.is_empty() {
+            write!(f, "{}", integer)
+        } else {
+            write!(f, "{}.{}", integer, fraction)
+        }
+    }
+}
+
+impl PartialOrd for TokenAmount {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self.decimals == other.decimals {
+            Some(self.raw.cmp(&other.raw))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn amount(raw: u64, decimals: u8) -> TokenAmount {
+        TokenAmount::new(U256::from(raw), decimals)
+    }
+
+    #[test]
+    fn parse_fraction_without_integer_part() {
+        assert_eq!(
+            TokenAmount::parse(".5", 18),
+            Ok(amount(500_000_000_000_000_000, 18))
+        );
+    }
+
+    #[test]
+    fn parse_integer_with_trailing_point() {
+        assert_eq!(TokenAmount::parse("5.", 6), Ok(amount(5_000_000, 6)));
+    }
+
+    #[test]
+    fn parse_rejects_second_point() {
+        assert_eq!(
+            TokenAmount::parse("1.2.3", 18),
+            Err(ParseTokenAmountError::InvalidDigit("1.2.3".to_owned()))
+        );
+    }
+
+    #[test]
+    fn parse_ignores_trailing_zeros_beyond_precision() {
+        assert_eq!(TokenAmount::parse("1.2500000000", 2), Ok(amount(125, 2)));
+    }
+
+    #[test]
+    fn parse_rejects_too_many_decimals() {
+        assert_eq!(
+            TokenAmount::parse("1.234", 2),
+            Err(ParseTokenAmountError::TooManyDecimals { given: 3, max: 2 })
+        );
+    }
+
+    #[test]
+    fn parse_rejects_overflow() {
+        let value = format!("1{}", "0".repeat(78));
+        assert_eq!(
+            TokenAmount::parse(&value, 0),
+            Err(ParseTokenAmountError::Overflow)
+        );
+    }
+
+    #[test]
+    fn parse_zero() {
+        assert_eq!(TokenAmount::parse("0.000", 6), Ok(TokenAmount::zero(6)));
+        assert_eq!(TokenAmount::parse("000", 0), Ok(TokenAmount::zero(0)));
+    }
+
+    #[test]
+    fn parse_rejects_empty_and_bare_point() {
+        assert_eq!(
+            TokenAmount::parse("  ", 18),
+            Err(ParseTokenAmountError::Empty)
+        );
+        assert_eq!(
+            TokenAmount::parse(".", 18),
+            Err(ParseTokenAmountError::InvalidDigit(".".to_owned()))
+        );
+    }
+
+    #[test]
+    fn display_trims_fraction() {
+        assert_eq!(amount(1_500_000, 6).to_string(), "1.5");
+        assert_eq!(amount(5, 6).to_string(), "0.000005");
+        assert_eq!(amount(2_000_000, 6).to_string(), "2");
+    }
+
+    #[test]
+    fn display_zero() {
+        assert_eq!(TokenAmount::zero(18).to_string(), "0");
+        assert_eq!(TokenAmount::zero(0).to_string(), "0");
+    }
+
+    #[test]
+    fn display_without_decimals() {
+        assert_eq!(amount(1234, 0).to_string(), "1234");
+    }
+
+    #[test]
+    fn display_round_trips_through_parse() {
+        let original = amount(123_456_789, 8);
+        assert_eq!(TokenAmount::parse(&original.to_string(), 8), Ok(original));
+    }
+
+    #[test]
+    fn checked_arithmetic_rejects_mismatch_and_underflow() {
+        assert_eq!(amount(1, 6).checked_add(&amount(1, 18)), None);
+        assert_eq!(amount(1, 6).checked_sub(&amount(2, 6)), None);
+        assert_eq!(
+            amount(1, 6).saturating_sub(&amount(2, 6)),
+            TokenAmount::zero(6)
+        );
+        assert_eq!(amount(3, 6).checked_sub(&amount(2, 6)), Some(amount(1, 6)));
+    }
+
+    #[test]
+    fn rescale_up_and_down() {
+        assert_eq!(amount(15, 1).rescale(3), Some(amount(1500, 3)));
+        assert_eq!(amount(1599, 3).rescale(1), Some(amount(15, 1)));
+        assert_eq!(amount(7, 6).rescale(6), Some(amount(7, 6)));
+    }
+
+    #[test]
+    fn rescale_beyond_u256_precision() {
+        assert_eq!(TokenAmount::zero(0).rescale(100), None);
+        assert_eq!(amount(1, 0).rescale(78), None);
+        assert_eq!(
+            amount(1, 0).rescale(77).map(|a| a.raw()),
+            Some(U256::exp10(77))
+        );
+        assert_eq!(
+            TokenAmount::new(U256::MAX, 100).rescale(0),
+            Some(TokenAmount::zero(0))
+        );
+    }
+}
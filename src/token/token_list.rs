@@ -0,0 +1,395 @@
+// token_list.rs
+
+use super::token::{BlockChain, Token};
+use ethers::{providers::Middleware, types::Address, utils::to_checksum};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+
+#[derive(Debug)]
+pub enum TokenListError {
+    Json(serde_json::Error),
+    /// The listed address is not a 20-byte hex address.
+    InvalidAddress(String),
+    /// The listed address is not in EIP-55 checksum form.
+    BadChecksum {
+        listed: String,
+        expected: String,
+    },
+    SymbolMismatch {
+        address: Address,
+        listed: String,
+        on_chain: String,
+    },
+    DecimalsMismatch {
+        address: Address,
+        listed: u8,
+        on_chain: u8,
+    },
+}
+
+impl fmt::Display for TokenListError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenListError::Json(err) => write!(f, "Invalid token list: {}", err),
+            TokenListError::InvalidAddress(address) => {
+                write!(f, "Invalid token address: {}", address)
+            }
+            TokenListError::BadChecksum { listed, expected } => {
+                write!(
+                    f,
+                    "Token address {} is not checksummed, expected {}",
+                    listed, expected
+                )
+            }
+            TokenListError::SymbolMismatch {
+                address,
+                listed,
+                on_chain,
+            } => write!(
+                f,
+                "Token {:?} is listed as {} but its symbol is {}",
+                address, listed, on_chain
+            ),
+            TokenListError::DecimalsMismatch {
+                address,
+                listed,
+                on_chain,
+            } => write!(
+                f,
+                "Token {:?} is listed with {} decimals but has {}",
+                address, listed, on_chain
+            ),
+        }
+    }
+}
+
+impl Error for TokenListError {}
+
+impl From<serde_json::Error> for TokenListError {
+    fn from(err: serde_json::Error) -> Self {
+        TokenListError::Json(err)
+    }
+}
+
+/// One entry of a Uniswap token list (https://tokenlists.org).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenInfo {
+    pub chain_id: u64,
+    pub address: String,
+    pub symbol: String,
+    pub decimals: u8,
+    #[serde(default)]
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct TokenListFile {
+    #[serde(default)]
+    name: String,
+    tokens: Vec<TokenInfo>,
+}
+
+/// Tokens of one chain loaded from a token-list file, looked up by symbol or
+/// address.
+pub struct TokenList<M: Middleware> {
+    name: String,
+    block_chain: BlockChain,
+    tokens: Vec<Arc<Box<dyn Token<M>>>>,
+    listed_decimals: Vec<u8>,
+    by_symbol: HashMap<String, usize>,
+    by_address: HashMap<Address, usize>,
+}
+
+impl<M: Middleware> Clone for TokenList<M> {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            block_chain: self.block_chain.clone(),
+            tokens: self.tokens.clone(),
+            listed_decimals: self.listed_decimals.clone(),
+            by_symbol: self.by_symbol.clone(),
+            by_address: self.by_address.clone(),
+        }
+    }
+}
+
+impl<M: Middleware + 'static> TokenList<M> {
+    /// Parses a token-list JSON document and creates a `T` for every entry on
+    /// `block_chain`. Entries for other chains are skipped. When a symbol is
+    /// listed twice the first entry wins the symbol lookup.
+    pub fn from_json<T: Token<M> + 'static>(
+        json: &str,
+        block_chain: BlockChain,
+        provider: Arc<M>,
+    ) -> Result<Self, TokenListError> {
+        let file: TokenListFile = serde_json::from_str(json)?;
+        let chain_id = block_chain.chain_id();
+
+        let mut token_list = Self {
+            name: file.name,
+            block_chain: block_chain.clone(),
+            tokens: vec![],
+            listed_decimals: vec![],
+            by_symbol: HashMap::new(),
+            by_address: HashMap::new(),
+        };
+
+        for info in file
+            .tokens
+            .into_iter()
+            .filter(|info| info.chain_id == chain_id)
+        {
+            let address = parse_checksummed_address(&info.address)?;
+            if token_list.by_address.contains_key(&address) {
+                log::warn!("Token {} is listed twice, keeping the first", info.address);
+                continue;
+            }
+
+            let index = token_list.tokens.len();
+            if token_list.by_symbol.contains_key(&info.symbol) {
+                log::warn!(
+                    "Symbol {} is listed twice, {} is only found by address",
+                    info.symbol,
+                    info.address
+                );
+            } else {
+                token_list.by_symbol.insert(info.symbol.clone(), index);
+            }
+            token_list.by_address.insert(address, index);
+
+            let token = T::new(
+                block_chain.clone(),
+                provider.clone(),
+                address,
+                info.symbol,
+                Some(info.decimals),
+            );
+            token_list.tokens.push(Arc::new(Box::new(token)));
+            token_list.listed_decimals.push(info.decimals);
+        }
+
+        Ok(token_list)
+    }
+
+    /// Initializes every token and checks its on-chain `symbol` and
+    /// `decimals` against the list. Tokens that cannot be read or do not
+    /// match are dropped from the list and returned with the reason; the
+    /// others stay usable.
+    pub async fn initialize(&mut self) -> Vec<(Address, Box<dyn Error + Send + Sync>)> {
+        let mut tokens = Vec::with_capacity(self.tokens.len());
+        let mut listed_decimals = Vec::with_capacity(self.tokens.len());
+        let mut failures = vec![];
+        for (token, decimals) in self.tokens.iter().zip(&self.listed_decimals) {
+            match verify_token(token.as_ref().as_ref(), *decimals).await {
+                Ok(initialized) => {
+                    tokens.push(Arc::new(initialized));
+                    listed_decimals.push(*decimals);
+                }
+                Err(err) => {
+                    log::warn!(
+                        "Dropping {} ({:?}) from the token list: {}",
+                        token.symbol_name(),
+                        token.address(),
+                        err
+                    );
+                    failures.push((token.address(), err));
+                }
+            }
+        }
+
+        self.tokens = tokens;
+        self.listed_decimals = listed_decimals;
+        self.by_symbol.clear();
+        self.by_address.clear();
+        for (index, token) in self.tokens.iter().enumerate() {
+            self.by_symbol
+                .entry(token.symbol_name().to_owned())
+                .or_insert(index);
+            self.by_address.insert(token.address(), index);
+        }
+        failures
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn block_chain(&self) -> BlockChain {
+        self.block_chain.clone()
+    }
+
+    pub fn tokens(&self) -> &[Arc<Box<dyn Token<M>>>] {
+        &self.tokens
+    }
+
+    pub fn by_symbol(&self, symbol: &str) -> Option<&Arc<Box<dyn Token<M>>>> {
+        self.by_symbol.get(symbol).map(|index| &self.tokens[*index])
+    }
+
+    pub fn by_address(&self, address: Address) -> Option<&Arc<Box<dyn Token<M>>>> {
+        self.by_address
+            .get(&address)
+            .map(|index| &self.tokens[*index])
+    }
+}
+
+/// An initialized copy of `token`, checked against its listed decimals and
+/// symbol.
+async fn verify_token<M: Middleware + 'static>(
+    token: &dyn Token<M>,
+    listed_decimals: u8,
+) -> Result<Box<dyn Token<M>>, Box<dyn Error + Send + Sync>> {
+    let mut initialized = token.clone_box();
+    initialized.initialize().await?;
+
+    let on_chain_decimals = initialized.required_decimals()?;
+    if on_chain_decimals != listed_decimals {
+        return Err(Box::new(TokenListError::DecimalsMismatch {
+            address: initialized.address(),
+            listed: listed_decimals,
+            on_chain: on_chain_decimals,
+        }));
+    }
+
+    let on_chain_symbol = initialized.fetch_symbol().await?;
+    if on_chain_symbol != initialized.symbol_name() {
+        return Err(Box::new(TokenListError::SymbolMismatch {
+            address: initialized.address(),
+            listed: initialized.symbol_name().to_owned(),
+            on_chain: on_chain_symbol,
+        }));
+    }
+    Ok(initialized)
+}
+
+fn parse_checksummed_address(listed: &str) -> Result<Address, TokenListError> {
+    let address: Address = listed
+        .parse()
+        .map_err(|_| TokenListError::InvalidAddress(listed.to_owned()))?;
+    let expected = to_checksum(&address, None);
+    if listed != expected {
+        return Err(TokenListError::BadChecksum {
+            listed: listed.to_owned(),
+            expected,
+        });
+    }
+    Ok(address)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::token::BscToken;
+    use ethers::abi::{encode, Token as AbiToken};
+    use ethers::providers::{MockProvider, Provider};
+    use ethers::types::Bytes;
+
+    const BSC: BlockChain = BlockChain::BscChain { chain_id: 56 };
+    const BUSD: &str = "0xe9e7CEA3DedcA5984780Bafc599bD69ADd087D56";
+    const CAKE: &str = "0x0E09FaBB73Bd3Ade0a17ECC321fD13a19e81cE82";
+    const MKR: &str = "0x5f0Da599BB2ccCfcf6Fdfd7D81743B6020864350";
+
+    fn entry(chain_id: u64, address: &str, symbol: &str, decimals: u8) -> String {
+        format!(
+            r#"{{"chainId":{},"address":"{}","symbol":"{}","decimals":{}}}"#,
+            chain_id, address, symbol, decimals
+        )
+    }
+
+    fn token_list(entries: &[String]) -> Result<TokenList<Provider<MockProvider>>, TokenListError> {
+        let json = format!(r#"{{"name":"Test","tokens":[{}]}}"#, entries.join(","));
+        let (provider, _) = Provider::mocked();
+        TokenList::from_json::<BscToken<_>>(&json, BSC, Arc::new(provider))
+    }
+
+    #[test]
+    fn parses_checksummed_addresses_only() {
+        assert_eq!(
+            parse_checksummed_address(BUSD).unwrap(),
+            BUSD.parse::<Address>().unwrap()
+        );
+        assert!(matches!(
+            parse_checksummed_address(&BUSD.to_lowercase()),
+            Err(TokenListError::BadChecksum { expected, .. }) if expected == BUSD
+        ));
+        assert!(matches!(
+            parse_checksummed_address("0x1234"),
+            Err(TokenListError::InvalidAddress(_))
+        ));
+    }
+
+    #[test]
+    fn keeps_only_the_chain_s_tokens() {
+        let list = token_list(&[entry(56, BUSD, "BUSD", 18), entry(1, CAKE, "CAKE", 18)]).unwrap();
+        assert_eq!(list.name(), "Test");
+        assert_eq!(list.tokens().len(), 1);
+        assert!(list.by_symbol("BUSD").is_some());
+        assert!(list.by_symbol("CAKE").is_none());
+    }
+
+    #[test]
+    fn first_entry_wins_duplicates() {
+        let list = token_list(&[
+            entry(56, BUSD, "BUSD", 18),
+            entry(56, BUSD, "BUSD2", 18),
+            entry(56, CAKE, "BUSD", 18),
+        ])
+        .unwrap();
+        assert_eq!(list.tokens().len(), 2);
+        assert!(list.by_symbol("BUSD2").is_none());
+
+        let busd = BUSD.parse().unwrap();
+        let cake = CAKE.parse().unwrap();
+        assert_eq!(list.by_symbol("BUSD").unwrap().address(), busd);
+        assert_eq!(list.by_address(cake).unwrap().symbol_name(), "BUSD");
+    }
+
+    #[test]
+    fn rejects_a_bad_checksum() {
+        assert!(matches!(
+            token_list(&[entry(56, &BUSD.to_lowercase(), "BUSD", 18)]),
+            Err(TokenListError::BadChecksum { .. })
+        ));
+    }
+
+    #[test]
+    fn initialize_drops_failing_tokens() {
+        let json = format!(
+            r#"{{"name":"Test","tokens":[{},{},{}]}}"#,
+            entry(56, BUSD, "BUSD", 18),
+            entry(56, CAKE, "CAKE", 18),
+            entry(56, MKR, "MKR", 18)
+        );
+        let (provider, mock) = Provider::mocked();
+        let mut list = TokenList::from_json::<BscToken<_>>(&json, BSC, Arc::new(provider)).unwrap();
+
+        let decimals = |decimals: u8| Bytes::from(encode(&[AbiToken::Uint(decimals.into())]));
+        let symbol = |symbol: &str| Bytes::from(encode(&[AbiToken::String(symbol.to_owned())]));
+        // The mock answers the last pushed response first
+        let mut responses = vec![
+            decimals(18),
+            symbol("BUSD"),
+            decimals(9),
+            decimals(18),
+            // MKR returns its symbol as bytes32, which does not decode as a string
+            Bytes::from(encode(&[AbiToken::FixedBytes(b"MKR".to_vec())])),
+        ];
+        responses.reverse();
+        for response in responses {
+            mock.push::<Bytes, _>(response).unwrap();
+        }
+
+        let failures = futures::executor::block_on(list.initialize());
+
+        let failed: Vec<Address> = failures.iter().map(|(address, _)| *address).collect();
+        assert_eq!(failed, vec![CAKE.parse().unwrap(), MKR.parse().unwrap()]);
+        assert_eq!(list.tokens().len(), 1);
+        assert_eq!(list.by_symbol("BUSD").unwrap().decimals(), Some(18));
+        assert!(list.by_symbol("CAKE").is_none());
+        assert!(list.by_address(MKR.parse().unwrap()).is_none());
+    }
+}
@@ -0,0 +1,269 @@
+// wrapped_native_token.rs
+
+use super::permit::PermitSignature;
+use super::token::{wait_for_success, AnchorToken, BlockChain, Token};
+use crate::fee_policy::FeePolicy;
+use crate::signer::Signing;
+use ethers::{
+    contract::{parse_log, EthEvent},
+    providers::Middleware,
+    types::{Address, TransactionReceipt, U256},
+};
+use std::{error::Error, sync::Arc};
+
+/// WETH9 `Deposit` event, emitted by `deposit`.
+#[derive(Debug, Clone, PartialEq, Eq, EthEvent)]
+#[ethevent(name = "Deposit", abi = "Deposit(address,uint256)")]
+struct DepositEvent {
+    #[ethevent(indexed)]
+    dst: Address,
+    wad: U256,
+}
+
+/// WETH9 `Withdrawal` event, emitted by `withdraw`.
+#[derive(Debug, Clone, PartialEq, Eq, EthEvent)]
+#[ethevent(name = "Withdrawal", abi = "Withdrawal(address,uint256)")]
+struct WithdrawalEvent {
+    #[ethevent(indexed)]
+    src: Address,
+    wad: U256,
+}
+
+/// WBNB/WMATIC/WETH: an ERC-20 that wraps the chain's native currency
+/// through the WETH9 `deposit` and `withdraw` functions.
+pub struct WrappedNativeToken<M: Middleware> {
+    anchor_token: AnchorToken<M>,
+}
+
+impl<M: Middleware> Clone for WrappedNativeToken<M> {
+    fn clone(&self) -> Self {
+        Self {
+            anchor_token: self.anchor_token.clone(),
+        }
+    }
+}
+
+impl<M: Middleware + 'static> WrappedNativeToken<M> {
+    /// Deposits `amount` of native currency and checks that the mined
+    /// transaction's `Deposit` event credits the wallet with exactly that
+    /// amount.
+    pub async fn wrap(&self, amount: U256) -> Result<(), Box<dyn Error + Send + Sync>>
+    where
+        M: Signing,
+    {
+        let contract = self.anchor_token.token_contract()?;
+        let owner = contract.client_ref().signer_address();
+
+        let mut call = contract.method::<_, ()>("deposit", ())?.value(amount);
+        self.anchor_token
+            .fee_policy()
+            .apply(contract.client_ref(), &mut call.tx)
+            .await?;
+        let receipt = wait_for_success(
+            call.send().await?,
+            "deposit",
+            &self.anchor_token.block_chain(),
+        )
+        .await?;
+
+        let deposited = self.event_total::<DepositEvent>(&receipt, |event| {
+            (event.dst == owner).then_some(event.wad)
+        });
+        verify_event_amount(deposited, amount, "deposit")
+    }
+
+    /// Withdraws `amount` of the wrapped token back to native currency and
+    /// checks that the mined transaction's `Withdrawal` event debits the
+    /// wallet by exactly that amount.
+    pub async fn unwrap(&self, amount: U256) -> Result<(), Box<dyn Error + Send + Sync>>
+    where
+        M: Signing,
+    {
+        let contract = self.anchor_token.token_contract()?;
+        let owner = contract.client_ref().signer_address();
+        let balance_before = self.anchor_token.balance_of(owner).await?;
+        if balance_before < amount {
+            return Err(Box::new(std::io::Error::other(format!(
+                "Cannot unwrap {} {}, balance is {}",
+                amount,
+                self.anchor_token.symbol_name(),
+                balance_before
+            ))));
+        }
+
+        let mut call = contract.method::<_, ()>("withdraw", amount)?;
+        self.anchor_token
+            .fee_policy()
+            .apply(contract.client_ref(), &mut call.tx)
+            .await?;
+        let receipt = wait_for_success(
+            call.send().await?,
+            "withdraw",
+            &self.anchor_token.block_chain(),
+        )
+        .await?;
+
+        let withdrawn = self.event_total::<WithdrawalEvent>(&receipt, |event| {
+            (event.src == owner).then_some(event.wad)
+        });
+        verify_event_amount(withdrawn, amount, "withdraw")
+    }
+
+    /// Sum of the amounts `select` picks from this token's `E` events in
+    /// `receipt`. Other activity in the same block does not count. A bogus
+    /// total saturates and then fails `verify_event_amount`.
+    fn event_total<E: EthEvent>(
+        &self,
+        receipt: &TransactionReceipt,
+        select: impl Fn(E) -> Option<U256>,
+    ) -> U256 {
+        receipt
+            .logs
+            .iter()
+            .filter(|log| log.address == self.anchor_token.address())
+            .filter_map(|log| parse_log::<E>(log.clone()).ok())
+            .filter_map(select)
+            .fold(U256::zero(), |total, amount| total.saturating_add(amount))
+    }
+}
+
+fn verify_event_amount(
+    amount: U256,
+    expected: U256,
+    method: &str,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if amount != expected {
+        return Err(Box::new(std::io::Error::other(format!(
+            "{} moved {} wrapped tokens, expected {}",
+            method, amount, expected
+        ))));
+    }
+    Ok(())
+}
+
+#[async_trait::async_trait]
+impl<M: Middleware + 'static> Token<M> for WrappedNativeToken<M> {
+    fn new(
+        block_chain: BlockChain,
+        provider: Arc<M>,
+        address: Address,
+        symbol_name: String,
+        decimals: Option<u8>,
+    ) -> Self {
+        Self {
+            anchor_token: AnchorToken::new(block_chain, provider, address, symbol_name, decimals),
+        }
+    }
+
+    fn clone_box(&self) -> Box<dyn Token<M>> {
+        Box::new(self.clone())
+    }
+
+    fn block_chain(&self) -> BlockChain {
+        self.anchor_token.block_chain()
+    }
+
+    // Delegate the implementation of common methods to the AnchorToken
+    fn block_chain_id(&self) -> u64 {
+        self.anchor_token.block_chain_id()
+    }
+
+    fn address(&self) -> Address {
+        self.anchor_token.address()
+    }
+
+    fn symbol_name(&self) -> &str {
+        self.anchor_token.symbol_name()
+    }
+
+    fn decimals(&self) -> Option<u8> {
+        self.anchor_token.decimals()
+    }
+
+    fn is_fee_on_transfer(&self) -> bool {
+        self.anchor_token.is_fee_on_transfer()
+    }
+
+    fn set_fee_on_transfer(&mut self, fee_on_transfer: bool) {
+        self.anchor_token.set_fee_on_transfer(fee_on_transfer)
+    }
+
+    fn fee_policy(&self) -> &FeePolicy {
+        self.anchor_token.fee_policy()
+    }
+
+    fn set_fee_policy(&mut self, fee_policy: FeePolicy) {
+        self.anchor_token.set_fee_policy(fee_policy)
+    }
+
+    async fn initialize(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.anchor_token.initialize().await
+    }
+
+    async fn approve(
+        &self,
+        spender: Address,
+        amount: U256,
+    ) -> Result<(), Box<dyn Error + Send + Sync>>
+    where
+        M: Signing,
+    {
+        self.anchor_token.approve(spender, amount).await
+    }
+
+    async fn approve_and_wait(
+        &self,
+        spender: Address,
+        amount: U256,
+    ) -> Result<(), Box<dyn Error + Send + Sync>>
+    where
+        M: Signing,
+    {
+        self.anchor_token.approve_and_wait(spender, amount).await
+    }
+
+    async fn supports_permit(&self, owner: Address) -> bool {
+        self.anchor_token.supports_permit(owner).await
+    }
+
+    async fn sign_permit(
+        &self,
+        spender: Address,
+        value: U256,
+        deadline: U256,
+    ) -> Result<PermitSignature, Box<dyn Error + Send + Sync>>
+    where
+        M: Signing,
+    {
+        self.anchor_token
+            .sign_permit(spender, value, deadline)
+            .await
+    }
+
+    async fn fetch_symbol(&self) -> Result<String, Box<dyn Error + Send + Sync>> {
+        self.anchor_token.fetch_symbol().await
+    }
+
+    async fn allowance(
+        &self,
+        owner: Address,
+        spender: Address,
+    ) -> Result<U256, Box<dyn Error + Send + Sync>> {
+        self.anchor_token.allowance(owner, spender).await
+    }
+
+    async fn balance_of(&self, owner: Address) -> Result<U256, Box<dyn Error + Send + Sync>> {
+        self.anchor_token.balance_of(owner).await
+    }
+
+    async fn transfer(
+        &self,
+        recipient: Address,
+        amount: U256,
+    ) -> Result<(), Box<dyn Error + Send + Sync>>
+    where
+        M: Signing,
+    {
+        self.anchor_token.transfer(recipient, amount).await
+    }
+}